@@ -0,0 +1,223 @@
+//! Synchronous facade over [`ImmuDB`] for non-async callers (CLIs,
+//! scripts). Only the most common calls are mirrored; anything else can be
+//! driven through [`BlockingImmuDB::block_on`].
+
+use std::future::Future;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use tokio::runtime::Runtime;
+
+use crate::ImmuDB;
+use crate::Result;
+use crate::document::{DocClient, InsertedIds, builder::SearchDocuments};
+use crate::model::{Collection, DocumentAtRevision};
+use crate::schema::{DatabaseInfo, SqlExecResult, TxMode};
+use crate::sql::{Params, QueryResult, SqlClient};
+
+/// Blocking handle to an immudb session. Must not be used from inside an
+/// async runtime: every call blocks the current thread.
+pub struct BlockingImmuDB {
+    db: ImmuDB,
+    /// Runs the channel's connection tasks; the session is closed on it
+    /// in `drop`
+    rt: Arc<Runtime>,
+}
+
+impl BlockingImmuDB {
+    /// Connect with default options; use
+    /// `ImmuDB::builder()...connect_blocking(uri)` to customize them.
+    /// Uri example: "http://localhost:3322"
+    pub fn connect(uri: impl AsRef<str>) -> Result<Self> {
+        ImmuDB::builder().connect_blocking(uri)
+    }
+
+    pub(crate) fn new(db: ImmuDB, rt: Arc<Runtime>) -> Self {
+        Self { db, rt }
+    }
+
+    /// Run any future of the async API on the internal runtime
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        self.rt.block_on(fut)
+    }
+
+    /// The wrapped async client
+    pub fn as_async(&self) -> &ImmuDB {
+        &self.db
+    }
+
+    pub fn sql(&self) -> BlockingSqlClient {
+        BlockingSqlClient {
+            inner: self.db.sql(),
+            rt: self.rt.clone(),
+        }
+    }
+
+    pub fn doc(&self) -> BlockingDocClient {
+        BlockingDocClient {
+            inner: self.db.doc(),
+            rt: self.rt.clone(),
+        }
+    }
+
+    pub fn use_database(&self, database: &str) -> Result<()> {
+        self.rt.block_on(self.db.use_database(database))
+    }
+
+    pub fn list_databases(&self) -> Result<Vec<DatabaseInfo>> {
+        self.rt.block_on(self.db.list_databases())
+    }
+}
+
+impl Drop for BlockingImmuDB {
+    fn drop(&mut self) {
+        // Close on `rt`, which drives the channel, rather than leave it to
+        // `ImmuDB`'s drop and the throwaway runtime it builds outside one
+        if let Err(e) = self.rt.block_on(self.db.close()) {
+            tracing::error!("failed to close immudb session: {e:?}");
+        }
+    }
+}
+
+/// A single worker thread rather than a current-thread runtime: the
+/// keepalive task and the channel's connection tasks must keep running
+/// between blocking calls, and closing the session on drop relies on them.
+pub(crate) fn runtime() -> Result<Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .map_err(|e| {
+            crate::error::Error::Unexpected(format!(
+                "failed to build tokio runtime: {e}"
+            ))
+        })
+}
+
+/// Blocking counterpart of [`SqlClient`]
+pub struct BlockingSqlClient {
+    inner: SqlClient,
+    rt: Arc<Runtime>,
+}
+
+impl BlockingSqlClient {
+    pub fn exec<P: Into<Params>>(
+        &mut self,
+        sql: impl Into<String>,
+        params: P,
+    ) -> Result<SqlExecResult> {
+        self.rt.block_on(self.inner.exec(sql, params))
+    }
+
+    pub fn query<P: Into<Params>>(
+        &mut self,
+        sql: impl Into<String>,
+        params: P,
+    ) -> Result<QueryResult> {
+        self.rt.block_on(self.inner.query(sql, params))
+    }
+
+    pub fn query_as<T: DeserializeOwned>(
+        &mut self,
+        sql: impl Into<String>,
+        params: impl Into<Params>,
+    ) -> Result<Vec<T>> {
+        self.rt.block_on(self.inner.query_as(sql, params.into()))
+    }
+
+    pub fn begin(&mut self, mode: impl Into<TxMode>) -> Result<()> {
+        self.rt.block_on(self.inner.begin(mode))
+    }
+
+    pub fn commit(&mut self) -> Result<()> {
+        self.rt.block_on(self.inner.commit())
+    }
+
+    pub fn rollback(&mut self) -> Result<()> {
+        self.rt.block_on(self.inner.rollback())
+    }
+
+    /// The wrapped async client
+    pub fn as_async(&mut self) -> &mut SqlClient {
+        &mut self.inner
+    }
+}
+
+/// Blocking counterpart of [`DocClient`]
+pub struct BlockingDocClient {
+    inner: DocClient,
+    rt: Arc<Runtime>,
+}
+
+impl BlockingDocClient {
+    pub fn list_collections(&self) -> Result<Vec<Collection>> {
+        self.rt.block_on(self.inner.list_collections())
+    }
+
+    pub fn insert_documents(
+        &mut self,
+        collection: &str,
+        docs: Vec<serde_json::Value>,
+    ) -> Result<InsertedIds> {
+        self.rt
+            .block_on(self.inner.insert_documents(collection, docs))
+    }
+
+    /// Run a query built with [`crate::document::builder::DocQuery`],
+    /// first page with the client defaults
+    pub fn search(
+        &self,
+        query: serde_json::Value,
+    ) -> Result<Vec<DocumentAtRevision>> {
+        self.rt
+            .block_on(SearchDocuments::query(query).execute(&self.inner))
+    }
+
+    /// The wrapped async client
+    pub fn as_async(&mut self) -> &mut DocClient {
+        &mut self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::Mock;
+    use crate::schema::{Column, Row, SqlQueryRequest, SqlQueryResult};
+
+    #[test]
+    fn query_and_close_on_drop() {
+        let server_rt = Runtime::new().unwrap();
+        let server = server_rt.block_on(
+            Mock::new()
+                .stream("SQLQuery", |_: SqlQueryRequest| {
+                    Ok(vec![SqlQueryResult {
+                        columns: vec![Column {
+                            name: "(n)".into(),
+                            r#type: "INTEGER".into(),
+                        }],
+                        rows: vec![Row {
+                            columns: vec!["(n)".into()],
+                            values: vec![crate::schema::SqlValue {
+                                value: Some(
+                                    crate::schema::sql_value::Value::N(7),
+                                ),
+                            }],
+                        }],
+                    }])
+                })
+                .start(),
+        );
+
+        let db = ImmuDB::builder()
+            .app_keepalive(false)
+            .connect_blocking(server.uri())
+            .unwrap();
+        let rows = db.sql().query("SELECT 7 AS n", Params::new()).unwrap();
+        assert_eq!(rows.scalar::<i64>().unwrap(), 7);
+
+        assert!(server.calls_to("CloseSession").is_empty());
+        drop(db);
+        assert_eq!(server.calls_to("CloseSession").len(), 1);
+    }
+}
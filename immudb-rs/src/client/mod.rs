@@ -1,14 +1,19 @@
-use std::sync::Arc;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
 
 use bon::Builder;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
+use tonic::codec::CompressionEncoding;
 use tonic::{service::interceptor::InterceptedService, transport::Channel};
 
 use crate::document::DocClient;
 use crate::error::Error;
-use crate::interceptor::SessionInterceptor;
+use crate::interceptor::{HeaderNames, SessionInterceptor};
+use crate::keyval::{KvClient, MemoryStateStore, StateStore};
+use crate::retry::RetryPolicy;
 use crate::schema::{DatabaseListRequestV2, DatabaseListResponseV2};
 use crate::sql::SqlClient;
 
@@ -21,6 +26,12 @@ use super::protocol::schema::immu_service_client::ImmuServiceClient;
 #[derive(Debug, Clone, Builder)]
 #[builder(finish_fn(vis = "", name = build_internal))]
 pub struct ConnectOptions {
+    /// Skip `use_database`: no database token is issued, for admin calls
+    /// (`list_databases`, ...) only. immudb still opens the session
+    /// against `database`. Set with [`ConnectOptionsBuilder::no_database`].
+    #[builder(field)]
+    pub no_database: bool,
+
     #[builder(into, default = String::from("immudb"))]
     pub username: String,
 
@@ -30,21 +41,133 @@ pub struct ConnectOptions {
     #[builder(into, default = String::from("defaultdb"))]
     pub database: String,
 
+    /// Limit for establishing the connection; see `request_timeout` for
+    /// the calls themselves
     #[builder(default = Duration::from_secs(5))]
     pub connect_timeout: Duration,
 
+    /// Default deadline of every call, sent to the server as well; none by
+    /// default. A request that sets its own `grpc-timeout` keeps it. For a
+    /// streamed query the deadline covers reading the whole stream.
+    pub request_timeout: Option<Duration>,
+
     #[builder(default = true)]
     pub keepalive_while_idle: bool,
+
+    /// Background task pinging the session every 30s so immudb doesn't
+    /// expire it while idle. Short-lived clients can turn it off; an
+    /// idle session then times out server-side, and `is_connected` no
+    /// longer tracks the session.
+    #[builder(default = true)]
+    pub app_keepalive: bool,
+
+    /// Max gRPC message size (both directions) for every service client.
+    /// Defaults to immudb's server-side limit (32 MiB).
+    #[builder(default = DEFAULT_MAX_MESSAGE_SIZE)]
+    pub max_message_size: usize,
+
+    /// Retry policy for transient errors; retrying is off by default
+    #[builder(default)]
+    pub retry: RetryPolicy,
+
+    /// Names of the session/auth metadata headers
+    #[builder(default)]
+    pub header_names: HeaderNames,
+
+    /// Extra attempts `connect` makes when the server is unreachable
+    /// (e.g. still starting up). Auth and other server errors fail at once.
+    #[builder(default = 0)]
+    pub connect_retries: u32,
+
+    #[builder(default = Duration::from_secs(1))]
+    pub connect_retry_delay: Duration,
+
+    /// Compression of requests and responses; off by default. Requests go
+    /// uncompressed if the server turns compressed ones down at connect.
+    #[builder(default)]
+    pub compression: Compression,
+
+    /// `user-agent` of every request; not applied by `connect_with_channel`
+    #[builder(into, default = String::from(DEFAULT_USER_AGENT))]
+    pub user_agent: String,
+
+    /// `sessionid` header value for [`ConnectOptionsBuilder::connect_with_token`]
+    /// (empty by default); sessions opened by `connect` get theirs from
+    /// the server
+    #[builder(into)]
+    pub session_id: Option<String>,
+
+    /// `immudb-uuid` header value for
+    /// [`ConnectOptionsBuilder::connect_with_token`] (empty by default)
+    #[builder(into)]
+    pub server_uuid: Option<String>,
+
+    /// Last verified state per database, for verified key-value calls;
+    /// in memory by default
+    #[builder(default = Arc::new(MemoryStateStore::default()))]
+    pub state_store: Arc<dyn StateStore>,
+}
+
+/// gRPC message compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+}
+
+impl Compression {
+    fn encoding(self) -> Option<CompressionEncoding> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some(CompressionEncoding::Gzip),
+        }
+    }
+}
+
+const DEFAULT_USER_AGENT: &str =
+    concat!("immudb-rs/", env!("CARGO_PKG_VERSION"));
+
+/// immudb's default `--max-recv-msg-size`
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 32 * 1024 * 1024;
+
+impl<State: connect_options_builder::State> ConnectOptionsBuilder<State> {
+    /// See [`ConnectOptions::no_database`]
+    pub fn no_database(mut self) -> Self {
+        self.no_database = true;
+        self
+    }
 }
 
 impl<State: connect_options_builder::IsComplete> ConnectOptionsBuilder<State> {
     /// Uri example: "http://localhost:3322"
     pub async fn connect(self, uri: impl AsRef<str>) -> Result<ImmuDB> {
+        self.connect_inner(uri, None).await
+    }
+
+    /// Connect with a pre-minted database token (e.g. from an auth proxy)
+    /// instead of `username`/`password`: no session is opened, `token` is
+    /// sent as is for `database`. There is no keepalive (and so no token
+    /// refresh), and nothing to close on drop.
+    pub async fn connect_with_token(
+        self,
+        token: impl Into<String>,
+        uri: impl AsRef<str>,
+    ) -> Result<ImmuDB> {
+        self.connect_inner(uri, Some(token.into())).await
+    }
+
+    async fn connect_inner(
+        self,
+        uri: impl AsRef<str>,
+        token: Option<String>,
+    ) -> Result<ImmuDB> {
         let uri = uri.as_ref().parse()?;
         let opts = self.build_internal();
 
         // No TLS currently
         let endpoint = Channel::builder(uri)
+            .user_agent(opts.user_agent.clone())?
             .connect_timeout(opts.connect_timeout)
             .keep_alive_while_idle(opts.keepalive_while_idle)
             // Little TCP keepalive, if enabled
@@ -54,44 +177,229 @@ impl<State: connect_options_builder::IsComplete> ConnectOptionsBuilder<State> {
                 None
             });
 
-        let channel = endpoint.connect().await.map_err(Error::from)?;
+        let mut attempt = 0;
+        loop {
+            let connect =
+                tokio::time::timeout(opts.connect_timeout, endpoint.connect());
+            let res = match connect.await {
+                Err(_) => Err(Error::ConnectTimeout(opts.connect_timeout)),
+                Ok(Err(e)) if is_timeout(&e) => {
+                    Err(Error::ConnectTimeout(opts.connect_timeout))
+                }
+                Ok(Ok(channel)) => match &token {
+                    None => open(opts.clone(), channel).await,
+                    Some(token) => {
+                        open_with_token(opts.clone(), channel, token.clone())
+                    }
+                },
+                Ok(Err(e)) => Err(Error::from(e)),
+            };
+            match res {
+                Err(e)
+                    if attempt < opts.connect_retries && is_unreachable(&e) =>
+                {
+                    attempt += 1;
+                    tracing::warn!(
+                        attempt,
+                        retries = opts.connect_retries,
+                        "immudb unreachable, retrying connect: {e}"
+                    );
+                    tokio::time::sleep(opts.connect_retry_delay).await;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Connect over a caller-configured `Channel` (custom load balancing,
+    /// proxies, in-process servers, ...). `connect_timeout` and
+    /// `keepalive_while_idle` are not applied: they are channel settings.
+    pub async fn connect_with_channel(
+        self,
+        channel: Channel,
+    ) -> Result<ImmuDB> {
+        open(self.build_internal(), channel).await
+    }
 
-        let schema::OpenSessionResponse {
-            session_id,
-            server_uuid,
-        } = ImmuServiceClient::new(channel.clone())
-            .open_session(schema::OpenSessionRequest {
-                username: opts.username.into_bytes(),
-                password: opts.password.into_bytes(),
-                database_name: opts.database.clone(),
-            })
-            .await
-            .map_err(Error::from)?
-            .into_inner();
+    /// Blocking variant of [`ConnectOptionsBuilder::connect`]
+    #[cfg(feature = "blocking")]
+    pub fn connect_blocking(
+        self,
+        uri: impl AsRef<str>,
+    ) -> Result<crate::blocking::BlockingImmuDB> {
+        let rt = Arc::new(crate::blocking::runtime()?);
+        let db = rt.block_on(self.connect(uri))?;
+        Ok(crate::blocking::BlockingImmuDB::new(db, rt))
+    }
+}
 
-        let interceptor = SessionInterceptor::new(&session_id, &server_uuid);
-        let service =
-            InterceptedService::new(channel.clone(), interceptor.clone());
+/// Transport-level failure worth retrying on connect
+fn is_unreachable(e: &Error) -> bool {
+    match e {
+        Error::Transport(_) | Error::ConnectTimeout(_) => true,
+        Error::Protocol(status) => status.code() == tonic::Code::Unavailable,
+        _ => false,
+    }
+}
 
-        let token = ImmuServiceClient::new(service.clone())
-            .use_database(schema::Database {
-                database_name: opts.database.clone(),
-            })
-            .await?
-            .into_inner()
-            .token;
+/// Connect error caused by `connect_timeout` running out
+fn is_timeout(e: &tonic::transport::Error) -> bool {
+    let mut source: Option<&dyn std::error::Error> = Some(e);
+    while let Some(err) = source {
+        if err
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|io| io.kind() == std::io::ErrorKind::TimedOut)
+        {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
 
-        interceptor.set_token(token)?;
+/// Skip the session: seed the interceptor with `token` right away
+fn open_with_token(
+    opts: ConnectOptions,
+    channel: Channel,
+    token: String,
+) -> Result<ImmuDB> {
+    let interceptor = SessionInterceptor::new(
+        opts.session_id.as_deref().unwrap_or_default(),
+        opts.server_uuid.as_deref().unwrap_or_default(),
+        &opts.header_names,
+    )?
+    .request_timeout(opts.request_timeout);
+    interceptor.set_database_token(&opts.database, token)?;
+    let service = InterceptedService::new(channel, interceptor.clone());
 
-        let (ka_cancel, _ka_handle) = spawn_keepalive(service.clone());
+    let compression = opts.compression.encoding();
+    Ok(ImmuDB {
+        inner: Arc::new(Inner {
+            service,
+            interceptor,
+            cancel: CancellationToken::new(),
+            keepalive: Mutex::new(None),
+            // No session of ours to close
+            closed: AtomicBool::new(true),
+            connected: Arc::new(AtomicBool::new(true)),
+            max_message_size: opts.max_message_size,
+            send_compression: compression,
+            accept_compression: compression,
+            retry: opts.retry,
+            state_store: opts.state_store,
+        }),
+    })
+}
 
-        Ok(ImmuDB {
-            inner: Arc::new(Inner {
-                service,
-                interceptor,
-                cancel: ka_cancel,
-            }),
-        })
+/// Open a session over `channel`, select the database and start keepalive
+async fn open(opts: ConnectOptions, channel: Channel) -> Result<ImmuDB> {
+    // Not intercepted yet: apply `request_timeout` by hand
+    let mut req = tonic::Request::new(schema::OpenSessionRequest {
+        username: opts.username.clone().into_bytes(),
+        password: opts.password.clone().into_bytes(),
+        database_name: opts.database.clone(),
+    });
+    if let Some(timeout) = opts.request_timeout {
+        req.set_timeout(timeout);
+    }
+    let schema::OpenSessionResponse {
+        session_id,
+        server_uuid,
+    } = ImmuServiceClient::new(channel.clone())
+        .max_decoding_message_size(opts.max_message_size)
+        .max_encoding_message_size(opts.max_message_size)
+        .open_session(req)
+        .await
+        .map_err(Error::from)?
+        .into_inner();
+
+    let interceptor =
+        SessionInterceptor::new(&session_id, &server_uuid, &opts.header_names)?
+            .request_timeout(opts.request_timeout);
+    let service = InterceptedService::new(channel.clone(), interceptor.clone());
+
+    let send_compression =
+        match prepare_session(&opts, &service, &interceptor).await {
+            Ok(send_compression) => send_compression,
+            Err(e) => {
+                // Connect may be retried: don't leave a session per attempt
+                if let Err(status) = ImmuServiceClient::new(service.clone())
+                    .close_session(())
+                    .await
+                {
+                    tracing::warn!(%status, "failed to close immudb session");
+                }
+                return Err(e);
+            }
+        };
+    let accept_compression = opts.compression.encoding();
+
+    let connected = Arc::new(AtomicBool::new(true));
+    let (ka_cancel, ka_handle) = if opts.app_keepalive {
+        let (cancel, handle) = spawn_keepalive(
+            service.clone(),
+            interceptor.clone(),
+            connected.clone(),
+            KEEPALIVE_PERIOD,
+        );
+        (cancel, Some(handle))
+    } else {
+        (CancellationToken::new(), None)
+    };
+
+    Ok(ImmuDB {
+        inner: Arc::new(Inner {
+            service,
+            interceptor,
+            cancel: ka_cancel,
+            keepalive: Mutex::new(ka_handle),
+            closed: AtomicBool::new(false),
+            connected,
+            max_message_size: opts.max_message_size,
+            send_compression,
+            accept_compression,
+            retry: opts.retry,
+            state_store: opts.state_store,
+        }),
+    })
+}
+
+/// Select the database of a freshly opened session and probe compression;
+/// returns the encoding requests are sent with
+async fn prepare_session(
+    opts: &ConnectOptions,
+    service: &InterceptedService<Channel, SessionInterceptor>,
+    interceptor: &SessionInterceptor,
+) -> Result<Option<CompressionEncoding>> {
+    // immudb needs both calls: OpenSession binds the session to a database
+    // the user has access to, while only UseDatabase issues the token data
+    // calls are authorized with.
+    // Both get `database`; a later `ImmuDB::use_database` replaces the
+    // token, so the database selected last is the one in use.
+    if !opts.no_database {
+        use_database(service, interceptor, &opts.database).await?;
+    }
+
+    let Some(enc) = opts.compression.encoding() else {
+        return Ok(None);
+    };
+    // Probe: a server without support for the encoding answers compressed
+    // requests with UNIMPLEMENTED
+    let probe = ImmuServiceClient::new(service.clone())
+        .send_compressed(enc)
+        .accept_compressed(enc)
+        .keep_alive(())
+        .await;
+    match probe {
+        Ok(_) => Ok(Some(enc)),
+        Err(status) if status.code() == tonic::Code::Unimplemented => {
+            tracing::warn!(
+                %status,
+                "immudb rejects compressed requests, sending uncompressed"
+            );
+            Ok(None)
+        }
+        Err(status) => Err(status.into()),
     }
 }
 
@@ -104,6 +412,17 @@ struct Inner {
     service: InterceptedService<Channel, SessionInterceptor>,
     interceptor: SessionInterceptor,
     cancel: CancellationToken,
+    /// Taken by [`ImmuDB::close`]
+    keepalive: Mutex<Option<JoinHandle<()>>>,
+    /// Set by [`ImmuDB::close`]: the session is already closed on drop
+    closed: AtomicBool,
+    /// Cleared by keepalive after `KEEPALIVE_MAX_FAILURES` failures in a row
+    connected: Arc<AtomicBool>,
+    max_message_size: usize,
+    send_compression: Option<CompressionEncoding>,
+    accept_compression: Option<CompressionEncoding>,
+    retry: RetryPolicy,
+    state_store: Arc<dyn StateStore>,
 }
 
 impl ImmuDB {
@@ -114,38 +433,182 @@ impl ImmuDB {
         &self,
     ) -> DocumentServiceClient<InterceptedService<Channel, SessionInterceptor>>
     {
-        DocumentServiceClient::new(self.inner.service.clone())
+        let mut cli = DocumentServiceClient::new(self.inner.service.clone())
+            .max_decoding_message_size(self.inner.max_message_size)
+            .max_encoding_message_size(self.inner.max_message_size);
+        if let Some(enc) = self.inner.send_compression {
+            cli = cli.send_compressed(enc);
+        }
+        if let Some(enc) = self.inner.accept_compression {
+            cli = cli.accept_compressed(enc);
+        }
+        cli
     }
+    #[allow(dead_code)]
     pub(crate) fn raw_auth(
         &self,
     ) -> AuthorizationServiceClient<
         InterceptedService<Channel, SessionInterceptor>,
     > {
-        AuthorizationServiceClient::new(self.inner.service.clone())
+        let mut cli =
+            AuthorizationServiceClient::new(self.inner.service.clone())
+                .max_decoding_message_size(self.inner.max_message_size)
+                .max_encoding_message_size(self.inner.max_message_size);
+        if let Some(enc) = self.inner.send_compression {
+            cli = cli.send_compressed(enc);
+        }
+        if let Some(enc) = self.inner.accept_compression {
+            cli = cli.accept_compressed(enc);
+        }
+        cli
     }
     pub(crate) fn raw_main(
         &self,
     ) -> ImmuServiceClient<InterceptedService<Channel, SessionInterceptor>>
     {
-        ImmuServiceClient::new(self.inner.service.clone())
+        let mut cli = ImmuServiceClient::new(self.inner.service.clone())
+            .max_decoding_message_size(self.inner.max_message_size)
+            .max_encoding_message_size(self.inner.max_message_size);
+        if let Some(enc) = self.inner.send_compression {
+            cli = cli.send_compressed(enc);
+        }
+        if let Some(enc) = self.inner.accept_compression {
+            cli = cli.accept_compressed(enc);
+        }
+        cli
+    }
+    /// The generated `ImmuService` client over this session, for RPCs the
+    /// crate doesn't wrap. Message size and compression settings apply,
+    /// the retry policy doesn't.
+    ///
+    /// ```no_run
+    /// # async fn example(db: immudb_rs::ImmuDB) -> immudb_rs::Result<()> {
+    /// use immudb_rs::schema::Table;
+    ///
+    /// let described = db
+    ///     .raw_service()
+    ///     .describe_table(Table { table_name: "users".into() })
+    ///     .await?
+    ///     .into_inner();
+    /// println!("{} columns", described.rows.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn raw_service(
+        &self,
+    ) -> ImmuServiceClient<InterceptedService<Channel, SessionInterceptor>>
+    {
+        self.raw_main()
+    }
+    pub(crate) fn retry_policy(&self) -> RetryPolicy {
+        self.inner.retry
+    }
+    pub(crate) fn interceptor(&self) -> SessionInterceptor {
+        self.inner.interceptor.clone()
+    }
+    pub(crate) fn state_store(&self) -> Arc<dyn StateStore> {
+        self.inner.state_store.clone()
     }
     pub fn sql(&self) -> SqlClient {
-        SqlClient::new(&self)
+        SqlClient::new(self)
     }
     pub fn doc(&self) -> DocClient {
-        DocClient::new(&self)
+        DocClient::new(self)
+    }
+    pub fn kv(&self) -> KvClient {
+        KvClient::new(self)
+    }
+    /// `false` once keepalive has failed `KEEPALIVE_MAX_FAILURES` (3) times
+    /// in a row, i.e. the session is likely dead; `true` again after the
+    /// next successful keepalive
+    pub fn is_connected(&self) -> bool {
+        self.inner.connected.load(Ordering::Relaxed)
     }
+    /// Session id assigned by the server in `open_session`
+    pub fn session_id(&self) -> &str {
+        self.inner.interceptor.session_id()
+    }
+    /// Server uuid reported in `open_session`
+    pub fn server_uuid(&self) -> &str {
+        self.inner.interceptor.server_uuid()
+    }
+    /// Database selected by the last `use_database` (including the one
+    /// made on connect); `None` for `no_database` clients
+    pub fn database(&self) -> Option<String> {
+        self.inner.interceptor.database()
+    }
+    /// Switch the session to `database`: data calls of every clone of
+    /// this `ImmuDB` go to it from now on
     pub async fn use_database(&self, database: &str) -> Result<()> {
-        let mut cli = ImmuServiceClient::new(self.inner.service.clone());
-        let resp = cli
-            .use_database(schema::Database {
-                database_name: database.to_string(),
-            })
-            .await?
-            .into_inner();
+        use_database(&self.inner.service, &self.inner.interceptor, database)
+            .await
+    }
+
+    /// Switch to `database`, run `f`, then switch back to the previous
+    /// database, whether `f` succeeds, fails, panics or is cancelled. The
+    /// session is shared: every clone of this `ImmuDB` sees `database`
+    /// while `f` runs. A `no_database` client has nothing to switch back
+    /// to and gets `Error::InvalidInput`; use `use_database` there.
+    pub async fn with_database<T, F>(&self, database: &str, f: F) -> Result<T>
+    where
+        F: for<'a> FnOnce(&'a ImmuDB) -> BoxFut<'a, T>,
+    {
+        let Some(previous) = self.inner.interceptor.database() else {
+            return Err(Error::InvalidInput(
+                "with_database needs a selected database to restore".into(),
+            ));
+        };
+        self.use_database(database).await?;
+        let previous = Some(previous);
+        let mut guard = RestoreDatabase { db: self, previous };
+        let res = f(self).await;
+        // Disarm the guard only once restored: cancelled mid-restore, or
+        // after a failed one, it switches back from `drop`
+        let restored = match &guard.previous {
+            Some(previous) => self.use_database(previous).await,
+            None => Ok(()),
+        };
+        if restored.is_ok() {
+            guard.previous = None;
+        }
+        let v = res?;
+        restored?;
+        Ok(v)
+    }
 
-        self.inner.interceptor.set_token(resp.token)?;
-        Ok(())
+    /// Stop keepalive, wait for the task to finish and close the session.
+    /// Every clone of this `ImmuDB` is unusable afterwards; dropping the
+    /// last one no longer closes the session again. A keepalive task that
+    /// panicked is reported as an error.
+    pub async fn close(&self) -> Result<()> {
+        if self.inner.closed.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        self.inner.cancel.cancel();
+        let handle = self
+            .inner
+            .keepalive
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let joined = match handle {
+            Some(handle) => handle.await,
+            None => Ok(()),
+        };
+        // Close the session even if keepalive failed: `closed` is already
+        // set, so drop won't do it
+        ImmuServiceClient::new(self.inner.service.clone())
+            .close_session(())
+            .await?;
+        joined.map_err(|e| {
+            Error::Unexpected(format!("keepalive task failed: {e}"))
+        })
+    }
+
+    /// Re-issue the database token for the current database. The keepalive
+    /// task does this by itself once the server starts rejecting the token.
+    pub async fn refresh_token(&self) -> Result<()> {
+        refresh_token(&self.inner.service, &self.inner.interceptor).await
     }
 }
 
@@ -160,21 +623,54 @@ impl ImmuDB {
     }
 }
 
+type BoxFut<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Switches back to `previous` if [`ImmuDB::with_database`] is unwound or
+/// cancelled before it has restored it itself, or its restore failed
+struct RestoreDatabase<'a> {
+    db: &'a ImmuDB,
+    previous: Option<String>,
+}
+
+impl Drop for RestoreDatabase<'_> {
+    fn drop(&mut self) {
+        let Some(previous) = self.previous.take() else {
+            return;
+        };
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            tracing::error!(%previous, "no runtime to restore immudb database");
+            return;
+        };
+        let db = self.db.clone();
+        handle.spawn(async move {
+            if let Err(e) = db.use_database(&previous).await {
+                tracing::error!(%previous, "failed to restore database: {e}");
+            }
+        });
+    }
+}
+
 impl Drop for Inner {
     fn drop(&mut self) {
         self.cancel.cancel();
+        if *self.closed.get_mut() {
+            return;
+        }
         let mut client = ImmuServiceClient::new(self.service.clone());
+        let close = async move {
+            if let Err(e) = client.close_session(()).await {
+                tracing::error!("failed to close immudb session: {e:?}");
+            }
+        };
+        // Inside a runtime: close in the background, blocking here could
+        // stall (or, on a current-thread runtime, deadlock) the caller
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(close);
+            return;
+        }
         let _ =
             std::thread::spawn(move || match tokio::runtime::Runtime::new() {
-                Ok(rt) => {
-                    rt.block_on(async {
-                        if let Err(e) = client.close_session(()).await {
-                            tracing::error!(
-                                "failed to close immudb session: {e:?}"
-                            );
-                        }
-                    });
-                }
+                Ok(rt) => rt.block_on(close),
                 Err(e) => {
                     tracing::error!("failed to spawn tokio runtime: {e}");
                 }
@@ -183,23 +679,83 @@ impl Drop for Inner {
     }
 }
 
+async fn use_database(
+    service: &InterceptedService<Channel, SessionInterceptor>,
+    interceptor: &SessionInterceptor,
+    database: &str,
+) -> Result<()> {
+    let resp = ImmuServiceClient::new(service.clone())
+        .use_database(schema::Database {
+            database_name: database.to_string(),
+        })
+        .await?
+        .into_inner();
+
+    interceptor.set_database_token(database, resp.token)
+}
+
+async fn refresh_token(
+    service: &InterceptedService<Channel, SessionInterceptor>,
+    interceptor: &SessionInterceptor,
+) -> Result<()> {
+    match interceptor.database() {
+        Some(database) => use_database(service, interceptor, &database).await,
+        None => Ok(()),
+    }
+}
+
+/// Consecutive keepalive failures after which the session counts as lost
+const KEEPALIVE_MAX_FAILURES: u32 = 3;
+
+const KEEPALIVE_PERIOD: Duration = Duration::from_secs(30);
+
 fn spawn_keepalive(
     service: InterceptedService<Channel, SessionInterceptor>,
+    interceptor: SessionInterceptor,
+    connected: Arc<AtomicBool>,
+    period: Duration,
 ) -> (CancellationToken, JoinHandle<()>) {
     let cancel = CancellationToken::new();
     let svc = service.clone();
     let handle = tokio::spawn({
         let cancel = cancel.clone();
         async move {
-            let mut cli = ImmuServiceClient::new(svc);
-            let mut tick = tokio::time::interval(Duration::from_secs(30));
+            let mut cli = ImmuServiceClient::new(svc.clone());
+            let mut tick = tokio::time::interval(period);
+            let mut failures = 0u32;
             loop {
                 tracing::trace!("keepalive tick");
                 tokio::select! {
                     _ = tick.tick() => {
-                        if let Err(e) = cli.keep_alive(()).await {
-                          tracing::warn!(%e, "immudb keepalive failed");
-                        }}
+                        let ok = match cli.keep_alive(()).await {
+                            Ok(_) => true,
+                            // The db token expired: re-issue it here, so
+                            // every client sharing the session picks it up
+                            Err(e) if e.code() == tonic::Code::Unauthenticated => {
+                                tracing::info!(%e, "immudb token rejected, refreshing");
+                                match refresh_token(&svc, &interceptor).await {
+                                    Ok(()) => true,
+                                    Err(e) => {
+                                        tracing::warn!("immudb token refresh failed: {e}");
+                                        false
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                tracing::warn!(%e, failures = failures + 1, "immudb keepalive failed");
+                                false
+                            }
+                        };
+                        failures = if ok { 0 } else { failures + 1 };
+                        let healthy = failures < KEEPALIVE_MAX_FAILURES;
+                        if connected.swap(healthy, Ordering::Relaxed) != healthy {
+                            if healthy {
+                                tracing::info!("immudb session reachable again");
+                            } else {
+                                tracing::error!(failures, "immudb session considered lost");
+                            }
+                        }
+                    }
                     _ = cancel.cancelled() => break,
                 }
             }
@@ -207,3 +763,682 @@ fn spawn_keepalive(
     });
     (cancel, handle)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::Mock;
+    use crate::schema::Database;
+
+    fn databases(calls: Vec<crate::mock::Call>) -> Vec<String> {
+        calls
+            .iter()
+            .map(|c| c.decode::<Database>().database_name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn with_database_restores_the_previous_database() {
+        let server = Mock::new().start().await;
+        let db = server.connect().await;
+
+        let inside = db
+            .with_database("other", |db| Box::pin(async { Ok(db.database()) }))
+            .await
+            .unwrap();
+        assert_eq!(inside.as_deref(), Some("other"));
+        assert_eq!(db.database().as_deref(), Some("defaultdb"));
+        assert_eq!(
+            databases(server.calls_to("UseDatabase")),
+            ["defaultdb", "other", "defaultdb"]
+        );
+
+        let err = db
+            .with_database("other", |_| {
+                Box::pin(async {
+                    Err::<(), _>(Error::Unexpected("boom".into()))
+                })
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Unexpected(_)));
+        assert_eq!(db.database().as_deref(), Some("defaultdb"));
+    }
+
+    #[tokio::test]
+    async fn with_database_restores_when_cancelled_while_restoring() {
+        let server = Mock::new()
+            .delay("UseDatabase", Duration::from_millis(200))
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        // Switching takes 200ms, so this fires halfway through the restore
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(300),
+            db.with_database("other", |_| Box::pin(async { Ok(()) })),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        assert_eq!(db.database().as_deref(), Some("other"));
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(db.database().as_deref(), Some("defaultdb"));
+        assert_eq!(
+            databases(server.calls_to("UseDatabase")),
+            ["defaultdb", "other", "defaultdb", "defaultdb"]
+        );
+    }
+
+    #[tokio::test]
+    async fn with_database_needs_a_selected_database() {
+        let server = Mock::new().start().await;
+        let db = ImmuDB::builder()
+            .app_keepalive(false)
+            .no_database()
+            .connect(server.uri())
+            .await
+            .unwrap();
+
+        let err = db
+            .with_database("other", |_| Box::pin(async { Ok(()) }))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)));
+        assert!(server.calls_to("UseDatabase").is_empty());
+    }
+
+    #[tokio::test]
+    async fn no_database_clients_list_databases() {
+        let server = Mock::new()
+            .unary("DatabaseListV2", |_: DatabaseListRequestV2| {
+                let info = |name: &str| schema::DatabaseInfo {
+                    name: name.into(),
+                    loaded: true,
+                    ..Default::default()
+                };
+                Ok(DatabaseListResponseV2 {
+                    databases: vec![info("defaultdb"), info("other")],
+                })
+            })
+            .start()
+            .await;
+        let db = ImmuDB::builder()
+            .app_keepalive(false)
+            .no_database()
+            .connect(server.uri())
+            .await
+            .unwrap();
+
+        assert_eq!(db.database(), None);
+        let names: Vec<_> = db
+            .list_databases()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(names, ["defaultdb", "other"]);
+        assert!(server.calls_to("UseDatabase").is_empty());
+        let list = &server.calls_to("DatabaseListV2")[0];
+        assert!(list.headers.get("authorization").is_none());
+    }
+
+    fn has_keepalive(db: &ImmuDB) -> bool {
+        db.inner.keepalive.lock().unwrap().is_some()
+    }
+
+    #[tokio::test]
+    async fn app_keepalive_controls_the_keepalive_task() {
+        let server = Mock::new().start().await;
+        let db = server.connect().await;
+        assert!(!has_keepalive(&db));
+        db.close().await.unwrap();
+
+        let db = ImmuDB::builder()
+            .app_keepalive(true)
+            .connect(server.uri())
+            .await
+            .unwrap();
+        assert!(has_keepalive(&db));
+        db.close().await.unwrap();
+        assert!(!has_keepalive(&db));
+    }
+
+    /// `UseDatabase` is unavailable for the first `failures` calls
+    async fn flaky_use_database(failures: u32) -> crate::mock::MockServer {
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        Mock::new()
+            .unary("UseDatabase", move |db: Database| {
+                if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                    return Err(tonic::Status::unavailable("starting up"));
+                }
+                Ok(schema::UseDatabaseReply {
+                    token: format!("token-{}", db.database_name),
+                })
+            })
+            .start()
+            .await
+    }
+
+    fn connect_with_retries(
+        server: &crate::mock::MockServer,
+    ) -> impl Future<Output = Result<ImmuDB>> {
+        ImmuDB::builder()
+            .app_keepalive(false)
+            .connect_retries(2)
+            .connect_retry_delay(Duration::from_millis(1))
+            .connect(server.uri())
+    }
+
+    #[tokio::test]
+    async fn connect_retries_and_closes_failed_sessions() {
+        let server = flaky_use_database(2).await;
+        let db = connect_with_retries(&server).await.unwrap();
+        assert_eq!(db.database().as_deref(), Some("defaultdb"));
+        assert_eq!(server.calls_to("OpenSession").len(), 3);
+        // One per failed attempt, none for the session in use
+        assert_eq!(server.calls_to("CloseSession").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn connect_gives_up_after_the_retries() {
+        let server = flaky_use_database(3).await;
+        let Err(err) = connect_with_retries(&server).await else {
+            panic!("connected to an unavailable server");
+        };
+        assert!(
+            matches!(&err, Error::Protocol(s) if s.code() == tonic::Code::Unavailable),
+            "{err:?}"
+        );
+        assert_eq!(server.calls_to("OpenSession").len(), 3);
+        assert_eq!(server.calls_to("CloseSession").len(), 3);
+    }
+
+    async fn connect_gzip(
+        mock: Mock,
+    ) -> (crate::mock::MockServer, Result<ImmuDB>) {
+        let server = mock
+            .unary("Get", |req: schema::KeyRequest| {
+                Ok(schema::Entry {
+                    value: req.key,
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let db = ImmuDB::builder()
+            .app_keepalive(false)
+            .compression(Compression::Gzip)
+            .connect(server.uri())
+            .await;
+        (server, db)
+    }
+
+    fn encoding(call: &crate::mock::Call) -> Option<&str> {
+        call.headers
+            .get("grpc-encoding")
+            .map(|v| v.to_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn compression_is_used_when_the_server_accepts_it() {
+        let (server, db) = connect_gzip(Mock::new()).await;
+        let db = db.unwrap();
+        assert_eq!(db.kv().get("key").await.unwrap(), Some(b"key".to_vec()));
+
+        assert_eq!(encoding(&server.calls_to("KeepAlive")[0]), Some("gzip"));
+        let get = &server.calls_to("Get")[0];
+        assert_eq!(encoding(get), Some("gzip"));
+        assert_eq!(get.decode::<schema::KeyRequest>().key, b"key");
+    }
+
+    #[tokio::test]
+    async fn compression_falls_back_on_unimplemented() {
+        let mock = Mock::new().reject_compressed(tonic::Code::Unimplemented);
+        let (server, db) = connect_gzip(mock).await;
+        let db = db.unwrap();
+        assert_eq!(db.kv().get("key").await.unwrap(), Some(b"key".to_vec()));
+
+        assert_eq!(encoding(&server.calls_to("KeepAlive")[0]), Some("gzip"));
+        assert_eq!(encoding(&server.calls_to("Get")[0]), None);
+    }
+
+    #[tokio::test]
+    async fn compression_probe_errors_fail_connect() {
+        let mock = Mock::new().reject_compressed(tonic::Code::Internal);
+        let (server, db) = connect_gzip(mock).await;
+        let Err(err) = db else {
+            panic!("connected despite the failed probe");
+        };
+        assert!(
+            matches!(&err, Error::Protocol(s) if s.code() == tonic::Code::Internal),
+            "{err:?}"
+        );
+        assert_eq!(server.calls_to("CloseSession").len(), 1);
+    }
+
+    /// Keepalive of `db` ticking every few milliseconds
+    fn fast_keepalive(
+        db: &ImmuDB,
+        connected: Arc<AtomicBool>,
+    ) -> (CancellationToken, JoinHandle<()>) {
+        spawn_keepalive(
+            db.inner.service.clone(),
+            db.inner.interceptor.clone(),
+            connected,
+            Duration::from_millis(5),
+        )
+    }
+
+    /// Poll `done` for up to 5 seconds
+    async fn eventually(mut done: impl FnMut() -> bool) {
+        for _ in 0..1000 {
+            if done() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached");
+    }
+
+    #[tokio::test]
+    async fn keepalive_refreshes_a_rejected_token() {
+        let (keepalives, tokens) = (
+            Arc::new(std::sync::atomic::AtomicU32::new(0)),
+            Arc::new(std::sync::atomic::AtomicU32::new(0)),
+        );
+        let server = Mock::new()
+            .unary("KeepAlive", move |_: ()| {
+                if keepalives.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return Err(tonic::Status::unauthenticated(
+                        "token expired",
+                    ));
+                }
+                Ok(())
+            })
+            .unary("UseDatabase", move |_: Database| {
+                let n = tokens.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(schema::UseDatabaseReply {
+                    token: format!("token-{n}"),
+                })
+            })
+            .unary("Get", |_: schema::KeyRequest| Ok(schema::Entry::default()))
+            .start()
+            .await;
+        let db = server.connect().await;
+        let connected = Arc::new(AtomicBool::new(true));
+        let (cancel, handle) = fast_keepalive(&db, connected.clone());
+
+        eventually(|| server.calls_to("UseDatabase").len() == 2).await;
+        cancel.cancel();
+        handle.await.unwrap();
+        assert!(connected.load(Ordering::SeqCst));
+        assert_eq!(
+            databases(server.calls_to("UseDatabase")),
+            ["defaultdb", "defaultdb"]
+        );
+
+        db.kv().get("k").await.unwrap();
+        let get = &server.calls_to("Get")[0];
+        assert_eq!(get.headers["authorization"], "token-2");
+    }
+
+    #[tokio::test]
+    async fn three_failed_keepalives_mark_the_session_lost() {
+        // Outcome of each keepalive in turn; later ones succeed
+        let script = [false, false, true, false, false, false, true, true];
+        let connected = Arc::new(AtomicBool::new(true));
+        // `connected` as seen by each keepalive, i.e. after the previous one
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let server = Mock::new()
+            .unary("KeepAlive", {
+                let (connected, seen) = (connected.clone(), seen.clone());
+                move |_: ()| {
+                    let mut seen = seen.lock().unwrap();
+                    seen.push(connected.load(Ordering::SeqCst));
+                    match script.get(seen.len() - 1) {
+                        Some(false) => {
+                            Err(tonic::Status::unavailable("unreachable"))
+                        }
+                        _ => Ok(()),
+                    }
+                }
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        let (cancel, handle) = fast_keepalive(&db, connected);
+
+        eventually(|| seen.lock().unwrap().len() > script.len()).await;
+        cancel.cancel();
+        handle.await.unwrap();
+        let seen = seen.lock().unwrap();
+        assert_eq!(
+            seen[..=script.len()],
+            [true, true, true, true, true, true, false, true, true]
+        );
+    }
+
+    /// Replace the keepalive task of `db` with `task`
+    fn set_keepalive(
+        db: &ImmuDB,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) {
+        *db.inner.keepalive.lock().unwrap() = Some(tokio::spawn(task));
+    }
+
+    #[tokio::test]
+    async fn close_waits_for_keepalive() {
+        let server = Mock::new().start().await;
+        let db = server.connect().await;
+        let finished = Arc::new(AtomicBool::new(false));
+        set_keepalive(&db, {
+            let cancel = db.inner.cancel.clone();
+            let finished = finished.clone();
+            async move {
+                cancel.cancelled().await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                finished.store(true, Ordering::SeqCst);
+            }
+        });
+
+        db.close().await.unwrap();
+        assert!(finished.load(Ordering::SeqCst));
+        assert_eq!(server.calls_to("CloseSession").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn close_reports_a_panicked_keepalive() {
+        let server = Mock::new().start().await;
+        let db = server.connect().await;
+        set_keepalive(&db, async { panic!("keepalive panicked") });
+
+        let err = db.close().await.unwrap_err();
+        assert!(
+            matches!(&err, Error::Unexpected(m) if m.starts_with("keepalive task failed")),
+            "{err:?}"
+        );
+        // The session is closed all the same
+        assert_eq!(server.calls_to("CloseSession").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn max_message_size_limits_responses() {
+        let server = Mock::new()
+            .unary("Get", |req: schema::KeyRequest| {
+                Ok(schema::Entry {
+                    key: req.key,
+                    value: vec![0; 64 * 1024],
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let connect = |max_message_size| {
+            ImmuDB::builder()
+                .app_keepalive(false)
+                .max_message_size(max_message_size)
+                .connect(server.uri())
+        };
+
+        let db = connect(16 * 1024).await.unwrap();
+        let err = db.kv().get("k").await.unwrap_err();
+        assert!(
+            matches!(&err, Error::Protocol(s) if s.code() == tonic::Code::OutOfRange),
+            "{err:?}"
+        );
+
+        let db = connect(128 * 1024).await.unwrap();
+        let value = db.kv().get("k").await.unwrap().unwrap();
+        assert_eq!(value.len(), 64 * 1024);
+    }
+
+    fn user_agent(call: &crate::mock::Call) -> &str {
+        call.headers["user-agent"].to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn user_agent_is_sent_with_every_request() {
+        let server = Mock::new().start().await;
+        server.connect().await;
+        let calls = server.calls();
+        assert!(!calls.is_empty());
+        for call in &calls {
+            assert!(user_agent(call).starts_with(DEFAULT_USER_AGENT));
+        }
+
+        let server = Mock::new().start().await;
+        ImmuDB::builder()
+            .app_keepalive(false)
+            .user_agent("my-app/1.0")
+            .connect(server.uri())
+            .await
+            .unwrap();
+        let open = &server.calls_to("OpenSession")[0];
+        assert!(user_agent(open).starts_with("my-app/1.0"));
+    }
+
+    #[tokio::test]
+    async fn slow_requests_time_out() {
+        let server = Mock::new()
+            .unary("Get", |_: schema::KeyRequest| Ok(schema::Entry::default()))
+            .delay("Get", Duration::from_secs(5))
+            .start()
+            .await;
+        let db = ImmuDB::builder()
+            .app_keepalive(false)
+            .request_timeout(Duration::from_millis(100))
+            .connect(server.uri())
+            .await
+            .unwrap();
+
+        let err = db.kv().get("k").await.unwrap_err();
+        assert!(matches!(err, Error::RequestTimeout(_)), "{err:?}");
+        for method in ["OpenSession", "UseDatabase", "Get"] {
+            let call = &server.calls_to(method)[0];
+            assert!(call.headers.contains_key("grpc-timeout"), "{method}");
+        }
+    }
+
+    #[tokio::test]
+    async fn unresponsive_servers_time_out_on_connect() {
+        // Never accepts: once its backlog is full, SYNs are dropped and
+        // the TCP handshake can't complete
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(1).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut backlog = Vec::new();
+        while let Ok(Ok(conn)) = tokio::time::timeout(
+            Duration::from_millis(100),
+            tokio::net::TcpStream::connect(addr),
+        )
+        .await
+        {
+            backlog.push(conn);
+        }
+
+        let timeout = Duration::from_millis(100);
+        let started = std::time::Instant::now();
+        let Err(err) = ImmuDB::builder()
+            .app_keepalive(false)
+            .connect_timeout(timeout)
+            .connect(format!("http://{addr}"))
+            .await
+        else {
+            panic!("connected to a full listener");
+        };
+        assert!(
+            matches!(err, Error::ConnectTimeout(t) if t == timeout),
+            "{err:?}"
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    fn get_server() -> Mock {
+        Mock::new()
+            .unary("Get", |_: schema::KeyRequest| Ok(schema::Entry::default()))
+    }
+
+    fn header<'a>(call: &'a crate::mock::Call, name: &str) -> &'a str {
+        call.headers[name].to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn token_clients_skip_the_session() {
+        let server = get_server().start().await;
+        let db = ImmuDB::builder()
+            .database("other")
+            .session_id("proxy-session")
+            .connect_with_token("minted", server.uri())
+            .await
+            .unwrap();
+        assert_eq!(db.database().as_deref(), Some("other"));
+        assert_eq!(db.session_id(), "proxy-session");
+        db.kv().get("k").await.unwrap();
+
+        let get = &server.calls_to("Get")[0];
+        assert_eq!(header(get, "authorization"), "minted");
+        assert_eq!(header(get, "sessionid"), "proxy-session");
+        assert_eq!(header(get, "immudb-uuid"), "");
+        for method in ["OpenSession", "UseDatabase", "KeepAlive"] {
+            assert!(server.calls_to(method).is_empty(), "{method}");
+        }
+
+        // Nothing to close
+        drop(db);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(server.calls_to("CloseSession").is_empty());
+    }
+
+    #[tokio::test]
+    async fn caller_channels_carry_the_session() {
+        let server = get_server().start().await;
+        let channel =
+            Channel::from_shared(server.uri()).unwrap().connect_lazy();
+        let db = ImmuDB::builder()
+            .app_keepalive(false)
+            .connect_with_channel(channel)
+            .await
+            .unwrap();
+        db.kv().get("k").await.unwrap();
+
+        assert_eq!(db.session_id(), crate::mock::SESSION_ID);
+        assert_eq!(databases(server.calls_to("UseDatabase")), ["defaultdb"]);
+        let get = &server.calls_to("Get")[0];
+        assert_eq!(header(get, "sessionid"), crate::mock::SESSION_ID);
+        assert_eq!(header(get, "authorization"), "token-defaultdb");
+
+        db.close().await.unwrap();
+        assert_eq!(server.calls_to("CloseSession").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn session_id_and_server_uuid_come_from_open_session() {
+        let server = Mock::new()
+            .unary("OpenSession", |_: schema::OpenSessionRequest| {
+                Ok(schema::OpenSessionResponse {
+                    session_id: "session-42".into(),
+                    server_uuid: "uuid-7".into(),
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        assert_eq!(db.session_id(), "session-42");
+        assert_eq!(db.server_uuid(), "uuid-7");
+
+        let use_db = &server.calls_to("UseDatabase")[0];
+        assert_eq!(header(use_db, "sessionid"), "session-42");
+        assert_eq!(header(use_db, "immudb-uuid"), "uuid-7");
+    }
+
+    #[tokio::test]
+    async fn renamed_headers_are_sent() {
+        let server = get_server().start().await;
+        let connect = |header_names| {
+            ImmuDB::builder()
+                .app_keepalive(false)
+                .header_names(header_names)
+                .connect(server.uri())
+        };
+        let db = connect(HeaderNames {
+            session_id: "x-session".into(),
+            server_uuid: "x-server".into(),
+            authorization: "x-token".into(),
+        })
+        .await
+        .unwrap();
+        db.kv().get("k").await.unwrap();
+
+        let get = &server.calls_to("Get")[0];
+        assert_eq!(header(get, "x-session"), crate::mock::SESSION_ID);
+        assert_eq!(header(get, "x-server"), crate::mock::SERVER_UUID);
+        assert_eq!(header(get, "x-token"), "token-defaultdb");
+        for default in ["sessionid", "immudb-uuid", "authorization"] {
+            assert!(!get.headers.contains_key(default), "{default}");
+        }
+
+        let Err(err) = connect(HeaderNames {
+            authorization: "not a header".into(),
+            ..HeaderNames::default()
+        })
+        .await
+        else {
+            panic!("connected with an invalid header name");
+        };
+        assert!(
+            matches!(&err, Error::InvalidInput(m) if m.contains("not a header")),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn dropping_inside_a_runtime_closes_in_the_background() {
+        let server = Mock::new().start().await;
+        let db = server.connect().await;
+        // Current-thread runtime: blocking in drop would deadlock
+        drop(db);
+        eventually(|| server.calls_to("CloseSession").len() == 1).await;
+    }
+
+    #[test]
+    fn dropping_outside_a_runtime_closes_the_session() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (server, db) = rt.block_on(async {
+            let server = Mock::new().start().await;
+            let db = server.connect().await;
+            (server, db)
+        });
+
+        // Closed on a runtime of its own before `drop` returns
+        drop(db);
+        assert_eq!(server.calls_to("CloseSession").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn the_last_selected_database_is_active() {
+        let server = get_server().start().await;
+        let db = ImmuDB::builder()
+            .app_keepalive(false)
+            .database("first")
+            .connect(server.uri())
+            .await
+            .unwrap();
+        let open = server.calls_to("OpenSession")[0]
+            .decode::<schema::OpenSessionRequest>();
+        assert_eq!(open.database_name, "first");
+        assert_eq!(db.database().as_deref(), Some("first"));
+
+        db.use_database("second").await.unwrap();
+        assert_eq!(db.database().as_deref(), Some("second"));
+        db.kv().get("k").await.unwrap();
+        assert_eq!(
+            databases(server.calls_to("UseDatabase")),
+            ["first", "second"]
+        );
+        let get = &server.calls_to("Get")[0];
+        assert_eq!(header(get, "authorization"), "token-second");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::document::DocClient;
+use crate::document::{DocClient, InsertedIds};
 
 use super::Result;
 
@@ -12,12 +12,18 @@ pub struct CreateCollection {
     pub(crate) name: String,
     #[builder(field)]
     pub(crate) fields: Vec<Field>,
-    /// Primary key for collection
+    /// Indexes beyond the single-field ones of `indexed` fields
+    #[builder(field)]
+    pub(crate) indexes: Vec<crate::model::Index>,
+    /// Primary key for collection; empty lets immudb use `_id`. immudb
+    /// creates this field itself, so it must not also be declared with
+    /// `field`: `create` fails with `Error::InvalidInput` before sending
+    /// such a collection, as immudb would reject it.
     #[builder(into, default = "")]
     pub(crate) document_id_field_name: String,
 }
 
-#[derive(Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum FieldType {
     #[default]
     String,
@@ -27,6 +33,41 @@ pub enum FieldType {
     Uuid,
 }
 
+impl FieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldType::String => "STRING",
+            FieldType::Boolean => "BOOLEAN",
+            FieldType::Integer => "INTEGER",
+            FieldType::Double => "DOUBLE",
+            FieldType::Uuid => "UUID",
+        }
+    }
+}
+
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Case-insensitive; `STR`, `BOOL`, `INT` and `FLOAT` are accepted too
+impl std::str::FromStr for FieldType {
+    type Err = crate::error::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_uppercase().as_str() {
+            "STRING" | "STR" => Ok(FieldType::String),
+            "BOOLEAN" | "BOOL" => Ok(FieldType::Boolean),
+            "INTEGER" | "INT" => Ok(FieldType::Integer),
+            "DOUBLE" | "FLOAT" => Ok(FieldType::Double),
+            "UUID" => Ok(FieldType::Uuid),
+            _ => Err(crate::error::Error::InvalidInput(format!(
+                "unknown field type: {s}"
+            ))),
+        }
+    }
+}
+
 #[derive(Default, bon::Builder)]
 #[builder(start_fn = name)]
 pub struct Field {
@@ -46,6 +87,20 @@ impl<S: create_collection_builder::State> CreateCollectionBuilder<S> {
         self.fields.push(arg);
         self
     }
+
+    /// Index over one or more declared fields, in key order. immudb
+    /// indexes have no sort direction: order is chosen per query.
+    pub fn index(
+        mut self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+        unique: bool,
+    ) -> Self {
+        self.indexes.push(crate::model::Index {
+            fields: fields.into_iter().map(Into::into).collect(),
+            is_unique: unique,
+        });
+        self
+    }
 }
 
 impl<S> CreateCollectionBuilder<S>
@@ -58,18 +113,58 @@ where
     }
 }
 
+// ──────────────────────────── Insert Documents ──────────────────────────── //
+
+#[derive(bon::Builder)]
+#[builder(start_fn = collection)]
+#[builder(finish_fn(vis = "", name = build_internal))]
+pub struct InsertDocuments {
+    #[builder(start_fn, into)]
+    pub(crate) collection: String,
+    #[builder(field)]
+    pub(crate) documents: Vec<serde_json::Value>,
+}
+
+impl<S: insert_documents_builder::State> InsertDocumentsBuilder<S> {
+    pub fn document(mut self, doc: serde_json::Value) -> Self {
+        self.documents.push(doc);
+        self
+    }
+
+    pub fn documents(
+        mut self,
+        docs: impl IntoIterator<Item = serde_json::Value>,
+    ) -> Self {
+        self.documents.extend(docs);
+        self
+    }
+}
+
+impl<S> InsertDocumentsBuilder<S>
+where
+    S: insert_documents_builder::IsComplete,
+{
+    pub async fn execute(self, doc: &mut DocClient) -> Result<InsertedIds> {
+        let param = self.build_internal();
+        doc.insert_documents(&param.collection, param.documents)
+            .await
+    }
+}
+
 // ──────────────────────────── Search Documents ──────────────────────────── //
 
 #[derive(bon::Builder)]
 #[builder(start_fn = query)]
 #[builder(finish_fn(vis = "", name = build_internal))]
 pub struct SearchDocuments {
-    #[builder(start_fn)]
-    pub(crate) query: serde_json::Value,
+    #[builder(start_fn, into)]
+    pub(crate) query: SearchQuery,
+    #[builder(field)]
+    pub(crate) allow_unbounded: bool,
     #[builder(into, default = "")]
     pub(crate) search_id: String,
-    #[builder(default = 50)]
-    pub(crate) page_size: u32,
+    /// Falls back to the `DocClient` default page size when unset
+    pub(crate) page_size: Option<u32>,
     #[builder(default = 1)]
     pub(crate) page: u32,
     /// Это поле нужно, чтобы явно указать Immudb сохранить состояние поиска на сервере.
@@ -78,13 +173,51 @@ pub struct SearchDocuments {
     pub(crate) keep_open: bool,
 }
 
+/// What [`SearchDocuments`] searches with
+#[derive(Debug, Clone)]
+pub enum SearchQuery {
+    /// Search JSON (see [`DocQuery`]), the default path
+    Json(serde_json::Value),
+    /// Pre-built protobuf query, sent as-is; an escape hatch for what the
+    /// JSON layer can't express
+    Raw(crate::model::Query),
+}
+
+impl From<serde_json::Value> for SearchQuery {
+    fn from(json: serde_json::Value) -> Self {
+        SearchQuery::Json(json)
+    }
+}
+
+impl From<crate::model::Query> for SearchQuery {
+    fn from(query: crate::model::Query) -> Self {
+        SearchQuery::Raw(query)
+    }
+}
+
+impl SearchDocuments {
+    /// Search with a hand-built `model::Query`, bypassing JSON parsing
+    pub fn raw_query(query: crate::model::Query) -> SearchDocumentsBuilder {
+        Self::query(SearchQuery::Raw(query))
+    }
+}
+
+impl<S: search_documents_builder::State> SearchDocumentsBuilder<S> {
+    /// Accept `"limit": 0` / `"limit": "all"` in the query: every matching
+    /// document is fetched, page by page from `page` on
+    pub fn allow_unbounded(mut self) -> Self {
+        self.allow_unbounded = true;
+        self
+    }
+}
+
 impl<S> SearchDocumentsBuilder<S>
 where
     S: search_documents_builder::IsComplete,
 {
     pub async fn execute(
         self,
-        doc: &mut DocClient,
+        doc: &DocClient,
     ) -> Result<Vec<crate::model::DocumentAtRevision>> {
         let mut param = self.build_internal();
 
@@ -95,3 +228,533 @@ where
         doc.search_document(param).await
     }
 }
+
+// ─────────────────────────────── Doc Query ──────────────────────────────── //
+
+/// Comparison operator of a document query; renders as (and parses from,
+/// case-insensitively) the operator strings of the search JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Like,
+    NotLike,
+    /// `EQ null`; takes no value
+    IsNull,
+    /// `NE null`; takes no value
+    IsNotNull,
+}
+
+impl Op {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Op::Eq => "EQ",
+            Op::Ne => "NE",
+            Op::Gt => "GT",
+            Op::Ge => "GE",
+            Op::Lt => "LT",
+            Op::Le => "LE",
+            Op::Like => "LIKE",
+            Op::NotLike => "NOT_LIKE",
+            Op::IsNull => "IS_NULL",
+            Op::IsNotNull => "IS_NOT_NULL",
+        }
+    }
+}
+
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Op {
+    type Err = crate::error::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_uppercase().as_str() {
+            "EQ" => Ok(Op::Eq),
+            "NE" => Ok(Op::Ne),
+            "GT" => Ok(Op::Gt),
+            "GE" => Ok(Op::Ge),
+            "LT" => Ok(Op::Lt),
+            "LE" => Ok(Op::Le),
+            "LIKE" => Ok(Op::Like),
+            "NOT_LIKE" => Ok(Op::NotLike),
+            "IS_NULL" => Ok(Op::IsNull),
+            "IS_NOT_NULL" => Ok(Op::IsNotNull),
+            _ => Err(crate::error::Error::InvalidInput(format!(
+                "Unknown comparison operator: {s}"
+            ))),
+        }
+    }
+}
+
+/// Single field comparison of a [`DocQuery`]
+#[derive(Debug, Clone)]
+pub struct Cmp {
+    field: String,
+    op: Op,
+    value: serde_json::Value,
+}
+
+impl Cmp {
+    pub fn new(
+        field: impl Into<String>,
+        op: Op,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            op,
+            value: value.into(),
+        }
+    }
+    pub fn eq(
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self::new(field, Op::Eq, value)
+    }
+    pub fn ne(
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self::new(field, Op::Ne, value)
+    }
+    pub fn gt(
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self::new(field, Op::Gt, value)
+    }
+    pub fn ge(
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self::new(field, Op::Ge, value)
+    }
+    pub fn lt(
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self::new(field, Op::Lt, value)
+    }
+    pub fn le(
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self::new(field, Op::Le, value)
+    }
+    pub fn like(
+        field: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self::new(field, Op::Like, value)
+    }
+
+    pub fn is_null(field: impl Into<String>) -> Self {
+        Self::new(field, Op::IsNull, serde_json::Value::Null)
+    }
+    pub fn is_not_null(field: impl Into<String>) -> Self {
+        Self::new(field, Op::IsNotNull, serde_json::Value::Null)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "field": self.field,
+            "op": self.op.as_str(),
+            "value": self.value,
+        })
+    }
+}
+
+/// Typed alternative to a hand-written search JSON.
+///
+/// `and` adds a comparison to the current group, `or` starts a new group;
+/// a document matches if it satisfies every comparison of any group.
+///
+/// ```no_run
+/// # async fn example(db: immudb_rs::ImmuDB) -> immudb_rs::Result<()> {
+/// use immudb_rs::document::builder::{Cmp, DocQuery, SearchDocuments};
+///
+/// let doc = db.doc();
+/// let query = DocQuery::collection("users")
+///     .and(Cmp::eq("is_active", true))
+///     .or(Cmp::gt("score", 10))
+///     .order_by("name", true)
+///     .limit(50)
+///     .build();
+/// SearchDocuments::query(query).execute(&doc).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(bon::Builder)]
+#[builder(start_fn = collection)]
+#[builder(finish_fn(vis = "", name = build_internal))]
+pub struct DocQuery {
+    #[builder(start_fn, into)]
+    pub(crate) collection_name: String,
+    #[builder(field)]
+    pub(crate) groups: Vec<Vec<Cmp>>,
+    #[builder(field)]
+    pub(crate) order_by: Vec<(String, bool)>,
+    pub(crate) limit: Option<u32>,
+}
+
+impl<S: doc_query_builder::State> DocQueryBuilder<S> {
+    pub fn and(mut self, cmp: Cmp) -> Self {
+        match self.groups.last_mut() {
+            Some(group) => group.push(cmp),
+            None => self.groups.push(vec![cmp]),
+        }
+        self
+    }
+    pub fn or(mut self, cmp: Cmp) -> Self {
+        self.groups.push(vec![cmp]);
+        self
+    }
+    pub fn order_by(mut self, field: impl Into<String>, desc: bool) -> Self {
+        self.order_by.push((field.into(), desc));
+        self
+    }
+}
+
+impl<S> DocQueryBuilder<S>
+where
+    S: doc_query_builder::IsComplete,
+{
+    /// Search JSON in the shape `SearchDocuments::query` consumes
+    pub fn build(self) -> serde_json::Value {
+        self.build_internal().to_json()
+    }
+}
+
+impl DocQuery {
+    fn to_json(&self) -> serde_json::Value {
+        let group_json = |group: &Vec<Cmp>| {
+            serde_json::json!({
+                "AND": group.iter().map(Cmp::to_json).collect::<Vec<_>>(),
+            })
+        };
+
+        let mut query = serde_json::Map::new();
+        query.insert(
+            "collection_name".into(),
+            self.collection_name.clone().into(),
+        );
+        if let Some(limit) = self.limit {
+            query.insert("limit".into(), limit.into());
+        }
+        if !self.order_by.is_empty() {
+            let order_by = self
+                .order_by
+                .iter()
+                .map(|(field, desc)| {
+                    serde_json::json!({"field": field, "desc": desc})
+                })
+                .collect::<Vec<_>>();
+            query.insert("order_by".into(), order_by.into());
+        }
+        match self.groups.as_slice() {
+            [] => {}
+            [group] => {
+                query.insert("where".into(), group_json(group));
+            }
+            groups => {
+                let groups = groups.iter().map(group_json).collect::<Vec<_>>();
+                query.insert("where".into(), serde_json::json!({"OR": groups}));
+            }
+        }
+        serde_json::Value::Object(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::document::conv::json_to_immudb_query;
+    use crate::document::tests::doc_method;
+    use crate::mock::Mock;
+    use crate::model::ComparisonOperator;
+
+    #[test]
+    fn doc_query_matches_the_hand_written_json() {
+        let query = DocQuery::collection("users")
+            .and(Cmp::eq("is_active", true))
+            .and(Cmp::is_not_null("email"))
+            .or(Cmp::gt("score", 10))
+            .order_by("name", false)
+            .order_by("score", true)
+            .limit(50)
+            .build();
+        assert_eq!(
+            query,
+            json!({
+                "collection_name": "users",
+                "limit": 50,
+                "order_by": [
+                    {"field": "name", "desc": false},
+                    {"field": "score", "desc": true},
+                ],
+                "where": {"OR": [
+                    {"AND": [
+                        {"field": "is_active", "op": "EQ", "value": true},
+                        {"field": "email", "op": "IS_NOT_NULL", "value": null},
+                    ]},
+                    {"AND": [{"field": "score", "op": "GT", "value": 10}]},
+                ]},
+            })
+        );
+
+        let q = json_to_immudb_query(query, 100, false).unwrap();
+        assert_eq!(q.collection_name, "users");
+        assert_eq!(q.limit, 50);
+        let groups: Vec<Vec<(&str, i32)>> = q
+            .expressions
+            .iter()
+            .map(|e| {
+                e.field_comparisons
+                    .iter()
+                    .map(|c| (c.field.as_str(), c.operator))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                vec![
+                    ("is_active", ComparisonOperator::Eq as i32),
+                    ("email", ComparisonOperator::Ne as i32),
+                ],
+                vec![("score", ComparisonOperator::Gt as i32)],
+            ]
+        );
+        let order: Vec<_> = q
+            .order_by
+            .iter()
+            .map(|o| (o.field.as_str(), o.desc))
+            .collect();
+        assert_eq!(order, [("name", false), ("score", true)]);
+    }
+
+    #[test]
+    fn single_group_and_no_where() {
+        let query = DocQuery::collection("users")
+            .and(Cmp::like("name", "^A"))
+            .build();
+        assert_eq!(
+            query,
+            json!({
+                "collection_name": "users",
+                "where": {"AND": [{"field": "name", "op": "LIKE", "value": "^A"}]},
+            })
+        );
+        assert_eq!(
+            DocQuery::collection("users").build(),
+            json!({"collection_name": "users"})
+        );
+    }
+
+    #[tokio::test]
+    async fn insert_documents_sends_every_document() {
+        let server = Mock::new()
+            .unary(
+                &doc_method("InsertDocuments"),
+                |req: crate::model::InsertDocumentsRequest| {
+                    Ok(crate::model::InsertDocumentsResponse {
+                        transaction_id: 3,
+                        document_ids: req
+                            .documents
+                            .iter()
+                            .enumerate()
+                            .map(|(i, _)| format!("id{i}"))
+                            .collect(),
+                    })
+                },
+            )
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut doc = db.doc();
+
+        let inserted = InsertDocuments::collection("users")
+            .document(json!({"name": "ann"}))
+            .documents(vec![json!({"name": "bob"}), json!({"name": "cy"})])
+            .execute(&mut doc)
+            .await
+            .unwrap();
+        assert_eq!(inserted.transaction_id, 3);
+        assert_eq!(inserted.ids(), ["id0", "id1", "id2"]);
+
+        let calls = server.calls_to(&doc_method("InsertDocuments"));
+        assert_eq!(calls.len(), 1);
+        let req = calls[0].decode::<crate::model::InsertDocumentsRequest>();
+        assert_eq!(req.collection_name, "users");
+        let names: Vec<_> = req
+            .documents
+            .iter()
+            .map(|d| d.fields["name"].kind.clone())
+            .collect();
+        let name =
+            |n: &str| Some(prost_types::value::Kind::StringValue(n.into()));
+        assert_eq!(names, [name("ann"), name("bob"), name("cy")]);
+    }
+
+    #[test]
+    fn field_types_round_trip_through_strings() {
+        use std::str::FromStr;
+        for ty in [
+            FieldType::String,
+            FieldType::Boolean,
+            FieldType::Integer,
+            FieldType::Double,
+            FieldType::Uuid,
+        ] {
+            assert_eq!(FieldType::from_str(&ty.to_string()).unwrap(), ty);
+            let lower = ty.to_string().to_lowercase();
+            assert_eq!(lower.parse::<FieldType>().unwrap(), ty);
+        }
+        assert_eq!("int".parse::<FieldType>().unwrap(), FieldType::Integer);
+        assert_eq!("FLOAT".parse::<FieldType>().unwrap(), FieldType::Double);
+        assert_ne!(FieldType::String, FieldType::Uuid);
+
+        let err = "DATE".parse::<FieldType>().unwrap_err();
+        assert!(
+            matches!(&err, crate::error::Error::InvalidInput(m) if m.contains("DATE"))
+        );
+    }
+
+    #[test]
+    fn ops_round_trip_through_strings() {
+        for op in [
+            Op::Eq,
+            Op::Ne,
+            Op::Gt,
+            Op::Ge,
+            Op::Lt,
+            Op::Le,
+            Op::Like,
+            Op::NotLike,
+            Op::IsNull,
+            Op::IsNotNull,
+        ] {
+            assert_eq!(op.to_string().parse::<Op>().unwrap(), op);
+            let lower = op.as_str().to_lowercase();
+            assert_eq!(lower.parse::<Op>().unwrap(), op);
+        }
+
+        let err = "BETWEEN".parse::<Op>().unwrap_err();
+        assert!(
+            matches!(&err, crate::error::Error::InvalidInput(m) if m.contains("BETWEEN"))
+        );
+    }
+
+    #[tokio::test]
+    async fn create_collection_sends_a_multi_field_index() {
+        let server = Mock::new()
+            .unary(
+                &doc_method("CreateCollection"),
+                |_: crate::model::CreateCollectionRequest| {
+                    Ok(crate::model::CreateCollectionResponse {})
+                },
+            )
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut doc = db.doc();
+        let field = |name: &str, field_type| {
+            Field::name(name).field_type(field_type).build()
+        };
+
+        CreateCollection::name("users")
+            .field(field("last", FieldType::String))
+            .field(field("first", FieldType::String))
+            .field(field("age", FieldType::Integer))
+            .index(["last", "first"], false)
+            .create(&mut doc)
+            .await
+            .unwrap();
+        let calls = server.calls_to(&doc_method("CreateCollection"));
+        let req = calls[0].decode::<crate::model::CreateCollectionRequest>();
+        assert_eq!(
+            req.indexes,
+            [crate::model::Index {
+                fields: vec!["last".into(), "first".into()],
+                is_unique: false,
+            }]
+        );
+
+        let err = CreateCollection::name("users")
+            .field(field("last", FieldType::String))
+            .index(["last", "first"], true)
+            .create(&mut doc)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, crate::error::Error::InvalidInput(m) if m.contains("'first'"))
+        );
+        assert_eq!(server.calls_to(&doc_method("CreateCollection")).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn raw_query_is_sent_as_is() {
+        use crate::model::{
+            FieldComparison, OrderByClause, Query, QueryExpression,
+            SearchDocumentsRequest, SearchDocumentsResponse,
+        };
+        let server = Mock::new()
+            .unary(
+                &doc_method("SearchDocuments"),
+                |_: SearchDocumentsRequest| {
+                    Ok(SearchDocumentsResponse::default())
+                },
+            )
+            .start()
+            .await;
+        let db = server.connect().await;
+        let doc = db.doc();
+
+        let cmp = |field: &str, op: ComparisonOperator| FieldComparison {
+            field: field.into(),
+            operator: op as i32,
+            value: Some(prost_types::Value {
+                kind: Some(prost_types::value::Kind::NumberValue(1.0)),
+            }),
+        };
+        let query = Query {
+            collection_name: "users".into(),
+            expressions: vec![
+                QueryExpression {
+                    field_comparisons: vec![
+                        cmp("a", ComparisonOperator::Gt),
+                        cmp("b", ComparisonOperator::Lt),
+                    ],
+                },
+                QueryExpression {
+                    field_comparisons: vec![cmp("c", ComparisonOperator::Eq)],
+                },
+            ],
+            order_by: vec![OrderByClause {
+                field: "a".into(),
+                desc: true,
+            }],
+            limit: 7,
+        };
+        SearchDocuments::raw_query(query.clone())
+            .page_size(5)
+            .execute(&doc)
+            .await
+            .unwrap();
+
+        let calls = server.calls_to(&doc_method("SearchDocuments"));
+        let req = calls[0].decode::<SearchDocumentsRequest>();
+        assert_eq!(req.query, Some(query));
+        assert_eq!(req.page_size, 5);
+    }
+}
@@ -1,12 +1,12 @@
 use crate::model;
 use crate::model::CreateCollectionRequest;
-use crate::model::Field;
 use crate::model::FieldType;
-use crate::model::Index;
 use crate::model::OrderByClause;
 use crate::model::Query;
 use serde_json::Value;
 
+use super::builder::Op;
+
 use crate::Result;
 use crate::error::Error;
 
@@ -23,6 +23,51 @@ impl From<super::builder::FieldType> for i32 {
     }
 }
 
+impl TryFrom<i32> for super::builder::FieldType {
+    type Error = Error;
+    fn try_from(val: i32) -> Result<Self> {
+        use super::builder::FieldType as Ft;
+        match FieldType::try_from(val) {
+            Ok(FieldType::String) => Ok(Ft::String),
+            Ok(FieldType::Boolean) => Ok(Ft::Boolean),
+            Ok(FieldType::Integer) => Ok(Ft::Integer),
+            Ok(FieldType::Double) => Ok(Ft::Double),
+            Ok(FieldType::Uuid) => Ok(Ft::Uuid),
+            Err(_) => Err(Error::Decode(format!("unknown field type {val}"))),
+        }
+    }
+}
+
+impl TryFrom<crate::model::Collection> for super::CollectionInfo {
+    type Error = Error;
+    fn try_from(c: crate::model::Collection) -> Result<Self> {
+        let fields = c
+            .fields
+            .into_iter()
+            .map(|f| {
+                Ok(super::FieldInfo {
+                    field_type: f.r#type.try_into()?,
+                    name: f.name,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let indexes = c
+            .indexes
+            .into_iter()
+            .map(|i| super::IndexInfo {
+                fields: i.fields,
+                unique: i.is_unique,
+            })
+            .collect();
+        Ok(super::CollectionInfo {
+            name: c.name,
+            document_id_field: c.document_id_field_name,
+            fields,
+            indexes,
+        })
+    }
+}
+
 pub struct ProtobufFieldParts {
     pub(super) proto_field: crate::model::Field,
     pub(super) proto_index: Option<crate::model::Index>,
@@ -51,18 +96,27 @@ impl From<super::builder::Field> for ProtobufFieldParts {
     }
 }
 
+/// `large_ints_as_strings`: integers beyond 2^53, which a protobuf
+/// `Value` (an f64) can't hold exactly, are stored as strings
 pub fn to_struct(
     json: serde_json::Map<String, serde_json::Value>,
+    large_ints_as_strings: bool,
 ) -> prost_types::Struct {
     prost_types::Struct {
         fields: json
             .into_iter()
-            .map(|(k, v)| (k, serde_json_to_prost(v)))
+            .map(|(k, v)| (k, serde_json_to_prost(v, large_ints_as_strings)))
             .collect(),
     }
 }
 
-fn serde_json_to_prost(json: serde_json::Value) -> prost_types::Value {
+/// Largest magnitude up to which every integer is exact in an f64
+const MAX_SAFE_INT: u64 = 1 << 53;
+
+fn serde_json_to_prost(
+    json: serde_json::Value,
+    large_ints_as_strings: bool,
+) -> prost_types::Value {
     use prost_types::value::Kind::*;
     use serde_json::Value::*;
     prost_types::Value {
@@ -70,17 +124,47 @@ fn serde_json_to_prost(json: serde_json::Value) -> prost_types::Value {
             Null => NullValue(0 /* wat? */),
             Bool(v) => BoolValue(v),
             Number(n) => {
-                NumberValue(n.as_f64().expect("Non-f64-representable number"))
+                let unsafe_int = n
+                    .as_i64()
+                    .map(|i| i.unsigned_abs() > MAX_SAFE_INT)
+                    .or_else(|| n.as_u64().map(|u| u > MAX_SAFE_INT))
+                    .unwrap_or(false);
+                if unsafe_int && large_ints_as_strings {
+                    StringValue(n.to_string())
+                } else {
+                    if unsafe_int {
+                        tracing::warn!(
+                            %n,
+                            "integer beyond 2^53 loses precision as a document \
+                             number, see DocClient::large_ints_as_strings"
+                        );
+                    }
+                    NumberValue(
+                        n.as_f64().expect("Non-f64-representable number"),
+                    )
+                }
             }
             String(s) => StringValue(s),
             Array(v) => ListValue(prost_types::ListValue {
-                values: v.into_iter().map(serde_json_to_prost).collect(),
+                values: v
+                    .into_iter()
+                    .map(|v| serde_json_to_prost(v, large_ints_as_strings))
+                    .collect(),
             }),
-            Object(v) => StructValue(to_struct(v)),
+            Object(v) => StructValue(to_struct(v, large_ints_as_strings)),
         }),
     }
 }
 
+pub fn struct_to_json(s: prost_types::Struct) -> serde_json::Value {
+    serde_json::Value::Object(
+        s.fields
+            .into_iter()
+            .map(|(k, v)| (k, prost_to_serde_json(v)))
+            .collect(),
+    )
+}
+
 fn prost_to_serde_json(x: prost_types::Value) -> serde_json::Value {
     use prost_types::value::Kind::*;
     use serde_json::Value::*;
@@ -88,23 +172,33 @@ fn prost_to_serde_json(x: prost_types::Value) -> serde_json::Value {
         Some(x) => match x {
             NullValue(_) => Null,
             BoolValue(v) => Bool(v),
-            NumberValue(n) => Number(serde_json::Number::from_f64(n).unwrap()),
+            // Whole numbers come back as integers, so they deserialize into
+            // integer types again; JSON has no NaN/infinity
+            NumberValue(n)
+                if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INT as f64 =>
+            {
+                Number((n as i64).into())
+            }
+            NumberValue(n) => {
+                serde_json::Number::from_f64(n).map_or(Null, Number)
+            }
             StringValue(s) => String(s),
             ListValue(lst) => {
                 Array(lst.values.into_iter().map(prost_to_serde_json).collect())
             }
-            StructValue(v) => Object(
-                v.fields
-                    .into_iter()
-                    .map(|(k, v)| (k, prost_to_serde_json(v)))
-                    .collect(),
-            ),
+            StructValue(v) => struct_to_json(v),
         },
-        None => panic!("todo"),
+        None => Null,
     }
 }
 
-pub fn json_to_immudb_query(json_query: Value) -> Result<Query> {
+/// `"limit": 0` or `"limit": "all"`: no limit, only accepted when
+/// `allow_unbounded` is set
+pub fn json_to_immudb_query(
+    json_query: Value,
+    default_limit: u32,
+    allow_unbounded: bool,
+) -> Result<Query> {
     let map = match json_query {
         Value::Object(m) => m,
         _ => {
@@ -116,47 +210,75 @@ pub fn json_to_immudb_query(json_query: Value) -> Result<Query> {
 
     let collection_name = map
         .get("collection_name")
-        .and_then(Value::as_str)
         .ok_or_else(|| Error::InvalidInput("Missing 'collection_name'".into()))?
+        .as_str()
+        .ok_or_else(|| {
+            Error::InvalidInput("'collection_name' must be a string".into())
+        })?
         .to_string();
 
-    let limit = map.get("limit").and_then(Value::as_u64).unwrap_or(100) as u32; // Устанавливаем разумный дефолт
-
-    let order_by = map
-        .get("order_by")
-        .and_then(Value::as_array)
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|item| {
-                    let m = item.as_object()?;
-                    let field = m.get("field")?.as_str()?.to_string();
-                    let desc =
-                        m.get("desc").and_then(Value::as_bool).unwrap_or(false);
-                    Some(OrderByClause { field, desc })
-                })
-                .collect()
-        })
-        .unwrap_or_default();
+    let limit =
+        match map.get("limit") {
+            None | Some(Value::Null) => default_limit,
+            Some(l) if l == 0 || l == "all" => {
+                if !allow_unbounded {
+                    return Err(Error::InvalidInput(
+                        "Unbounded 'limit' requires allow_unbounded()".into(),
+                    ));
+                }
+                0
+            }
+            Some(l) => {
+                l.as_u64().and_then(|l| u32::try_from(l).ok()).ok_or_else(
+                    || Error::InvalidInput(format!("Invalid 'limit': {l}")),
+                )?
+            }
+        };
+
+    let mut order_by = Vec::new();
+    if let Some(items) = map.get("order_by").filter(|o| !o.is_null()) {
+        for item in as_array(items, "order_by")? {
+            order_by.push(json_to_order_by(item)?);
+        }
+    }
 
+    // immudb ORs `expressions` together and ANDs the comparisons inside
+    // each of them, so `where` is read in disjunctive normal form:
+    // "AND" is a single group, "OR" lists alternative groups.
     let mut expressions = Vec::new();
-    if let Some(where_clause) = map.get("where").and_then(Value::as_object) {
-        // Does immudb use "AND" logic for expressions list?
-        // Try to find "AND" in WHERE.
-        if let Some(and_array) =
-            where_clause.get("AND").and_then(Value::as_array)
+    if let Some(where_clause) = map.get("where").filter(|w| !w.is_null()) {
+        let where_clause = where_clause.as_object().ok_or_else(|| {
+            Error::InvalidInput("'where' must be an object".into())
+        })?;
+        if let Some(key) =
+            where_clause.keys().find(|k| *k != "AND" && *k != "OR")
         {
-            for item in and_array {
-                if let Some(comparison_map) = item.as_object() {
-                    // Each FieldComparison becomes QueryExpression
-                    // in expressions list.
-                    let comparison = json_to_field_comparison(comparison_map)?;
-                    expressions.push(model::QueryExpression {
-                        field_comparisons: vec![comparison],
-                    });
-                }
+            return Err(Error::InvalidInput(format!(
+                "Unknown 'where' key: '{key}', expected 'AND' or 'OR'"
+            )));
+        }
+        if let Some(and_array) = where_clause.get("AND") {
+            expressions
+                .push(json_to_query_expression(as_array(and_array, "AND")?)?);
+        }
+        if let Some(or_array) = where_clause.get("OR") {
+            for item in as_array(or_array, "OR")? {
+                let item = item.as_object().ok_or_else(|| {
+                    Error::InvalidInput("'OR' items must be objects".into())
+                })?;
+                let expression = match item.get("AND") {
+                    Some(and_array) => {
+                        json_to_query_expression(as_array(and_array, "AND")?)?
+                    }
+                    None => model::QueryExpression {
+                        field_comparisons: vec![json_to_field_comparison(
+                            item,
+                        )?],
+                    },
+                };
+                expressions.push(expression);
             }
         }
-        // TODO: Can add "OR" logic or any other complex logic
     }
 
     Ok(Query {
@@ -167,59 +289,128 @@ pub fn json_to_immudb_query(json_query: Value) -> Result<Query> {
     })
 }
 
+fn json_to_order_by(item: &Value) -> Result<OrderByClause> {
+    let m = item.as_object().ok_or_else(|| {
+        Error::InvalidInput("'order_by' items must be objects".into())
+    })?;
+    if let Some(key) = m.keys().find(|k| *k != "field" && *k != "desc") {
+        return Err(Error::InvalidInput(format!(
+            "unknown key '{key}' in 'order_by' item, expected 'field' or 'desc'"
+        )));
+    }
+    let field = m.get("field").and_then(Value::as_str).ok_or_else(|| {
+        Error::InvalidInput("'order_by' item needs a string 'field'".into())
+    })?;
+    let desc = match m.get("desc") {
+        None | Some(Value::Null) => false,
+        Some(v) => v.as_bool().ok_or_else(|| {
+            Error::InvalidInput("'order_by' 'desc' must be a boolean".into())
+        })?,
+    };
+    Ok(OrderByClause {
+        field: field.into(),
+        desc,
+    })
+}
+
+fn json_to_query_expression(
+    and_array: &[Value],
+) -> Result<model::QueryExpression> {
+    let mut field_comparisons = Vec::with_capacity(and_array.len());
+    for item in and_array {
+        let comparison_map = item.as_object().ok_or_else(|| {
+            Error::InvalidInput("'AND' items must be objects".into())
+        })?;
+        field_comparisons.push(json_to_field_comparison(comparison_map)?);
+    }
+    Ok(model::QueryExpression { field_comparisons })
+}
+
+fn as_array<'a>(v: &'a Value, key: &str) -> Result<&'a [Value]> {
+    v.as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| Error::InvalidInput(format!("'{key}' must be an array")))
+}
+
 fn json_to_field_comparison(
     json_map: &serde_json::Map<String, Value>,
 ) -> Result<model::FieldComparison> {
     let field = json_map
         .get("field")
-        .and_then(Value::as_str)
         .ok_or_else(|| Error::InvalidInput("Missing 'field'".into()))?
-        .to_string();
+        .as_str()
+        .ok_or_else(|| {
+            Error::InvalidInput("'field' must be a string".into())
+        })?;
+    let field = normalize_field_path(field)?;
     let op = json_map
         .get("op")
-        .and_then(Value::as_str)
-        .ok_or_else(|| Error::InvalidInput("Missing 'op'".into()))?;
-    let value = json_map
-        .get("value")
-        .ok_or_else(|| Error::InvalidInput("Missing 'value'".into()))?
-        .clone();
+        .ok_or_else(|| Error::InvalidInput("Missing 'op'".into()))?
+        .as_str()
+        .ok_or_else(|| Error::InvalidInput("'op' must be a string".into()))?;
+    let op: Op = op.parse()?;
+    let value = match (op, json_map.get("value")) {
+        // Pseudo-operators: compare against null, the value is implied
+        (Op::IsNull | Op::IsNotNull, None | Some(Value::Null)) => Value::Null,
+        (Op::IsNull | Op::IsNotNull, Some(_)) => {
+            return Err(Error::InvalidInput(format!(
+                "'{op}' takes no 'value'"
+            )));
+        }
+        (_, None) => {
+            return Err(Error::InvalidInput("Missing 'value'".into()));
+        }
+        (Op::Eq | Op::Ne, Some(value)) => value.clone(),
+        (_, Some(Value::Null)) => {
+            return Err(Error::InvalidInput(format!(
+                "'{op}' can't compare against null, use IS_NULL/IS_NOT_NULL"
+            )));
+        }
+        (_, Some(value)) => value.clone(),
+    };
 
     Ok(model::FieldComparison {
         field,
-        operator: map_operator(op)?,
-        value: Some(serde_json_to_prost(value)),
+        operator: op.into(),
+        value: Some(serde_json_to_prost(value, false)),
     })
 }
 
-fn map_operator(op: &str) -> Result<i32> {
-    match op.to_uppercase().as_str() {
-        "EQ" => Ok(0), // ComparisonOperator::EQ as i32
-        "NE" => Ok(1), // ComparisonOperator::NE as i32
-        "GT" => Ok(2), // ComparisonOperator::GT as i32
-        "GE" => Ok(3), // ComparisonOperator::GE as i32
-        "LT" => Ok(4), // ComparisonOperator::LT as i32
-        "LE" => Ok(5), // ComparisonOperator::LE as i32
-        _ => Err(Error::InvalidInput(format!(
-            "Unknown comparison operator: {}",
-            op
-        ))),
+/// Nested document fields are addressed with dotted paths
+/// (`"address.city"`); immudb resolves them server-side, so the path is
+/// passed through as-is once every segment is checked to be non-empty.
+fn normalize_field_path(field: &str) -> Result<String> {
+    let segments = field.trim().split('.').map(str::trim).collect::<Vec<_>>();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(Error::InvalidInput(format!(
+            "Invalid field path: '{}'",
+            field
+        )));
     }
+    Ok(segments.join("."))
 }
 
-fn parse_field_type(type_str: &str) -> Result<FieldType> {
-    match type_str.to_uppercase().as_str() {
-        "STRING" | "STR" => Ok(FieldType::String),
-        "BOOLEAN" | "BOOL" => Ok(FieldType::Boolean),
-        "INTEGER" | "INT" => Ok(FieldType::Integer),
-        "DOUBLE" | "FLOAT" => Ok(FieldType::Double),
-        "UUID" => Ok(FieldType::Uuid),
-        _ => Err(Error::InvalidInput(format!(
-            "unknown field type: {}",
-            type_str
-        ))),
+impl From<super::builder::Op> for i32 {
+    fn from(op: super::builder::Op) -> Self {
+        use super::builder::Op;
+        use crate::model::ComparisonOperator;
+        let op = match op {
+            Op::Eq | Op::IsNull => ComparisonOperator::Eq,
+            Op::Ne | Op::IsNotNull => ComparisonOperator::Ne,
+            Op::Gt => ComparisonOperator::Gt,
+            Op::Ge => ComparisonOperator::Ge,
+            Op::Lt => ComparisonOperator::Lt,
+            Op::Le => ComparisonOperator::Le,
+            Op::Like => ComparisonOperator::Like,
+            Op::NotLike => ComparisonOperator::NotLike,
+        };
+        op as i32
     }
 }
 
+/// JSON schema (`name`, `document_id_field_name`, `fields`) to a request;
+/// goes through the same checks as [`super::builder::CreateCollection`]
+#[allow(dead_code)]
 pub fn json_to_create_collection_request(
     json_schema: Value,
 ) -> Result<CreateCollectionRequest> {
@@ -233,24 +424,25 @@ pub fn json_to_create_collection_request(
         .ok_or_else(|| Error::InvalidInput("Missing or invalid 'name'".into()))?
         .to_string();
 
-    let document_id_field_name = map
-        .get("document_id_field_name")
-        .and_then(Value::as_str)
-        .ok_or_else(|| {
-            Error::InvalidInput(
-                "Missing or invalid 'document_id_field_name'".into(),
-            )
-        })?
-        .to_string();
+    // Empty (or absent) lets immudb name the id field `_id`
+    let document_id_field_name = match map.get("document_id_field_name") {
+        None | Some(Value::Null) => String::new(),
+        Some(v) => v
+            .as_str()
+            .ok_or_else(|| {
+                Error::InvalidInput(
+                    "'document_id_field_name' must be a string".into(),
+                )
+            })?
+            .to_string(),
+    };
 
     let fields_json =
         map.get("fields").and_then(Value::as_array).ok_or_else(|| {
             Error::InvalidInput("Missing or invalid 'fields' array".into())
         })?;
 
-    let mut fields: Vec<Field> = Vec::new();
-    let mut indexes: Vec<Index> = Vec::new();
-
+    let mut fields = Vec::new();
     for field_def in fields_json {
         let def = field_def.as_object().ok_or_else(|| {
             Error::InvalidInput("Field definition must be an object".into())
@@ -258,38 +450,453 @@ pub fn json_to_create_collection_request(
         let field_name = def
             .get("name")
             .and_then(Value::as_str)
+            .filter(|name| !name.is_empty())
             .ok_or_else(|| Error::InvalidInput("Field 'name' missing".into()))?
             .to_string();
         let type_str =
             def.get("type").and_then(Value::as_str).ok_or_else(|| {
                 Error::InvalidInput("Field 'type' missing".into())
             })?;
+        let flag = |key| def.get(key).and_then(Value::as_bool).unwrap_or(false);
 
-        let field_type = parse_field_type(type_str)?;
-
-        fields.push(Field {
-            name: field_name.clone(),
-            r#type: field_type.into(),
+        fields.push(super::builder::Field {
+            name: field_name,
+            field_type: type_str.parse()?,
+            unique: flag("unique"),
+            indexed: flag("indexed"),
         });
+    }
 
-        if def.get("indexed").and_then(Value::as_bool).unwrap_or(false)
-            || field_name == document_id_field_name
-        {
-            let is_unique =
-                def.get("unique").and_then(Value::as_bool).unwrap_or(false)
-                    || field_name == document_id_field_name;
+    super::create_collection_request(super::builder::CreateCollection {
+        name,
+        fields,
+        indexes: Vec::new(),
+        document_id_field_name,
+    })
+}
 
-            indexes.push(Index {
-                fields: vec![field_name],
-                is_unique,
+#[cfg(test)]
+mod tests {
+    use prost_types::value::Kind;
+    use serde_json::json;
+
+    use super::*;
+    use crate::model::ComparisonOperator as Cmp;
+
+    fn query(json: Value) -> Result<Query> {
+        json_to_immudb_query(json, 100, false)
+    }
+
+    fn invalid(res: Result<Query>) -> String {
+        match res {
+            Err(Error::InvalidInput(message)) => message,
+            other => panic!("expected InvalidInput, got {other:?}"),
+        }
+    }
+
+    fn cmp(field: &str, op: &str, value: Value) -> Value {
+        json!({"field": field, "op": op, "value": value})
+    }
+
+    fn comparison(field: &str, op: Cmp, value: Kind) -> model::FieldComparison {
+        model::FieldComparison {
+            field: field.into(),
+            operator: op as i32,
+            value: Some(prost_types::Value { kind: Some(value) }),
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let null = || Kind::NullValue(0);
+        let cases = [
+            (cmp("age", "EQ", json!(3)), Cmp::Eq, Kind::NumberValue(3.0)),
+            (cmp("age", "ne", json!(3)), Cmp::Ne, Kind::NumberValue(3.0)),
+            (cmp("age", "GT", json!(3)), Cmp::Gt, Kind::NumberValue(3.0)),
+            (cmp("age", "GE", json!(3)), Cmp::Ge, Kind::NumberValue(3.0)),
+            (cmp("age", "LT", json!(3)), Cmp::Lt, Kind::NumberValue(3.0)),
+            (cmp("age", "LE", json!(3)), Cmp::Le, Kind::NumberValue(3.0)),
+            (
+                cmp("name", "LIKE", json!("^A")),
+                Cmp::Like,
+                Kind::StringValue("^A".into()),
+            ),
+            (
+                cmp("name", "NOT_LIKE", json!("^A")),
+                Cmp::NotLike,
+                Kind::StringValue("^A".into()),
+            ),
+            (cmp("name", "EQ", Value::Null), Cmp::Eq, null()),
+            (cmp("name", "NE", Value::Null), Cmp::Ne, null()),
+            (json!({"field": "name", "op": "IS_NULL"}), Cmp::Eq, null()),
+            (
+                json!({"field": "name", "op": "is_not_null"}),
+                Cmp::Ne,
+                null(),
+            ),
+        ];
+        for (where_cmp, op, value) in cases {
+            let field = where_cmp["field"].as_str().unwrap().to_string();
+            let q = query(json!({
+                "collection_name": "people",
+                "where": {"AND": [where_cmp.clone()]},
+            }))
+            .unwrap();
+            assert_eq!(
+                q.expressions,
+                [model::QueryExpression {
+                    field_comparisons: vec![comparison(&field, op, value)],
+                }],
+                "{where_cmp}"
+            );
+        }
+    }
+
+    #[test]
+    fn and_or_groups() {
+        let q = query(json!({
+            "collection_name": "people",
+            "where": {
+                "AND": [cmp("a", "EQ", json!(1)), cmp("b", "EQ", json!(2))],
+                "OR": [
+                    cmp("c", "EQ", json!(3)),
+                    {"AND": [cmp("d", "EQ", json!(4)), cmp("e", "EQ", json!(5))]},
+                ],
+            },
+        }))
+        .unwrap();
+        let fields: Vec<Vec<&str>> = q
+            .expressions
+            .iter()
+            .map(|e| {
+                e.field_comparisons
+                    .iter()
+                    .map(|c| c.field.as_str())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(fields, [vec!["a", "b"], vec!["c"], vec!["d", "e"]]);
+
+        let q = query(json!({"collection_name": "people"})).unwrap();
+        assert!(q.expressions.is_empty());
+        assert_eq!(q.limit, 100);
+    }
+
+    #[test]
+    fn malformed_queries() {
+        let people = |extra: Value| {
+            let mut q = json!({"collection_name": "people"});
+            q.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            q
+        };
+        let cases = [
+            (json!([]), "Query must be a JSON object"),
+            (json!({}), "Missing 'collection_name'"),
+            (
+                json!({"collection_name": 1}),
+                "'collection_name' must be a string",
+            ),
+            (people(json!({"limit": -1})), "Invalid 'limit': -1"),
+            (people(json!({"limit": "ten"})), "Invalid 'limit': \"ten\""),
+            (people(json!({"where": []})), "'where' must be an object"),
+            (
+                people(json!({"where": {"NOT": []}})),
+                "Unknown 'where' key: 'NOT', expected 'AND' or 'OR'",
+            ),
+            (
+                people(json!({"where": {"AND": {}}})),
+                "'AND' must be an array",
+            ),
+            (people(json!({"where": {"OR": 1}})), "'OR' must be an array"),
+            (
+                people(json!({"where": {"AND": [1]}})),
+                "'AND' items must be objects",
+            ),
+            (
+                people(json!({"where": {"OR": [1]}})),
+                "'OR' items must be objects",
+            ),
+            (
+                people(json!({"where": {"AND": [{"op": "EQ", "value": 1}]}})),
+                "Missing 'field'",
+            ),
+            (
+                people(json!({"where": {"AND": [
+                    {"field": 1, "op": "EQ", "value": 1}
+                ]}})),
+                "'field' must be a string",
+            ),
+            (
+                people(json!({"where": {"AND": [{"field": "a", "value": 1}]}})),
+                "Missing 'op'",
+            ),
+            (
+                people(json!({"where": {"AND": [{"field": "a", "op": 1}]}})),
+                "'op' must be a string",
+            ),
+            (
+                people(
+                    json!({"where": {"AND": [cmp("a", "BETWEEN", json!(1))]}}),
+                ),
+                "Unknown comparison operator: BETWEEN",
+            ),
+            (
+                people(json!({"where": {"AND": [{"field": "a", "op": "EQ"}]}})),
+                "Missing 'value'",
+            ),
+            (
+                people(
+                    json!({"where": {"AND": [cmp("a", "GT", Value::Null)]}}),
+                ),
+                "'GT' can't compare against null, use IS_NULL/IS_NOT_NULL",
+            ),
+            (
+                people(
+                    json!({"where": {"AND": [cmp("a", "IS_NULL", json!(1))]}}),
+                ),
+                "'IS_NULL' takes no 'value'",
+            ),
+        ];
+        for (q, expected) in cases {
+            assert_eq!(invalid(query(q.clone())), expected, "{q}");
+        }
+    }
+
+    #[test]
+    fn order_by() {
+        let q = query(json!({
+            "collection_name": "people",
+            "order_by": [
+                {"field": "age", "desc": true},
+                {"field": "name"},
+                {"field": "id", "desc": null},
+            ],
+        }))
+        .unwrap();
+        let order: Vec<(&str, bool)> = q
+            .order_by
+            .iter()
+            .map(|o| (o.field.as_str(), o.desc))
+            .collect();
+        assert_eq!(order, [("age", true), ("name", false), ("id", false)]);
+
+        let cases = [
+            (json!({"field": "age"}), "'order_by' must be an array"),
+            (json!(["age"]), "'order_by' items must be objects"),
+            (
+                json!([{"desc": true}]),
+                "'order_by' item needs a string 'field'",
+            ),
+            (
+                json!([{"field": 1}]),
+                "'order_by' item needs a string 'field'",
+            ),
+            (
+                json!([{"field": "age", "desc": "yes"}]),
+                "'order_by' 'desc' must be a boolean",
+            ),
+            (
+                json!([{"field": "age", "asc": true}]),
+                "unknown key 'asc' in 'order_by' item, expected 'field' or \
+                 'desc'",
+            ),
+        ];
+        for (order_by, expected) in cases {
+            let q = json!({"collection_name": "people", "order_by": order_by});
+            assert_eq!(invalid(query(q)), expected, "{order_by}");
+        }
+    }
+
+    #[test]
+    fn dotted_field_paths() {
+        for (field, expected) in [
+            ("city", "city"),
+            ("address.city", "address.city"),
+            (" address . city ", "address.city"),
+            ("a.b.c", "a.b.c"),
+        ] {
+            assert_eq!(normalize_field_path(field).unwrap(), expected);
+        }
+        for field in ["", " ", ".city", "address.", "address..city", "a. .b"] {
+            let q = json!({
+                "collection_name": "people",
+                "where": {"AND": [cmp(field, "EQ", json!(1))]},
             });
+            assert_eq!(
+                invalid(query(q)),
+                format!("Invalid field path: '{field}'")
+            );
         }
     }
 
-    Ok(CreateCollectionRequest {
-        name,
-        document_id_field_name,
-        fields,
-        indexes,
-    })
+    #[test]
+    fn unbounded_limit_needs_allow_unbounded() {
+        for limit in [json!(0), json!("all")] {
+            let q = json!({"collection_name": "people", "limit": limit});
+            assert_eq!(
+                invalid(json_to_immudb_query(q.clone(), 100, false)),
+                "Unbounded 'limit' requires allow_unbounded()"
+            );
+            assert_eq!(json_to_immudb_query(q, 100, true).unwrap().limit, 0);
+        }
+        for (limit, expected) in [(json!(null), 100), (json!(25), 25)] {
+            let q = json!({"collection_name": "people", "limit": limit});
+            assert_eq!(query(q).unwrap().limit, expected);
+        }
+        let q = json!({"collection_name": "people", "limit": u64::MAX});
+        assert!(invalid(query(q)).starts_with("Invalid 'limit'"));
+    }
+
+    fn to_prost(json: Value, large_ints_as_strings: bool) -> Kind {
+        serde_json_to_prost(json, large_ints_as_strings)
+            .kind
+            .unwrap()
+    }
+
+    #[test]
+    fn numbers_at_the_f64_boundaries() {
+        const SAFE: u64 = 1 << 53;
+        let cases = [
+            (json!(SAFE), Kind::NumberValue(SAFE as f64)),
+            (json!(-(SAFE as i64)), Kind::NumberValue(-(SAFE as f64))),
+            (
+                json!(SAFE + 1),
+                Kind::StringValue("9007199254740993".into()),
+            ),
+            (
+                json!(-(SAFE as i64) - 1),
+                Kind::StringValue("-9007199254740993".into()),
+            ),
+            (json!(i64::MIN), Kind::StringValue(i64::MIN.to_string())),
+            (json!(u64::MAX), Kind::StringValue(u64::MAX.to_string())),
+            (json!(1.5), Kind::NumberValue(1.5)),
+            (json!(1e300), Kind::NumberValue(1e300)),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(to_prost(json.clone(), true), expected, "{json}");
+        }
+
+        // Off: sent as (lossy) numbers
+        assert_eq!(
+            to_prost(json!(SAFE + 1), false),
+            Kind::NumberValue((SAFE + 1) as f64)
+        );
+        assert_eq!(
+            to_prost(json!(u64::MAX), false),
+            Kind::NumberValue(u64::MAX as f64)
+        );
+    }
+
+    #[test]
+    fn large_ints_as_strings_reaches_nested_values() {
+        let big = json!(u64::MAX);
+        let doc = json!({"a": big, "b": [big], "c": {"d": big}, "e": 1});
+        let Value::Object(doc) = doc else {
+            unreachable!()
+        };
+        let s = to_struct(doc, true);
+        let text = || Kind::StringValue(u64::MAX.to_string());
+        assert_eq!(s.fields["a"].kind, Some(text()));
+        let Some(Kind::ListValue(list)) = &s.fields["b"].kind else {
+            panic!("{:?}", s.fields["b"])
+        };
+        assert_eq!(list.values[0].kind, Some(text()));
+        let Some(Kind::StructValue(inner)) = &s.fields["c"].kind else {
+            panic!("{:?}", s.fields["c"])
+        };
+        assert_eq!(inner.fields["d"].kind, Some(text()));
+        assert_eq!(s.fields["e"].kind, Some(Kind::NumberValue(1.0)));
+    }
+
+    fn from_prost(kind: Option<Kind>) -> Value {
+        prost_to_serde_json(prost_types::Value { kind })
+    }
+
+    #[test]
+    fn prost_values_back_to_json() {
+        let cases = [
+            (Some(Kind::NumberValue(3.0)), json!(3)),
+            (Some(Kind::NumberValue(-0.0)), json!(0)),
+            (
+                Some(Kind::NumberValue((1u64 << 53) as f64)),
+                json!(1u64 << 53),
+            ),
+            // Beyond 2^53 a whole f64 may not be the integer that was sent
+            (Some(Kind::NumberValue(1e20)), json!(1e20)),
+            (Some(Kind::NumberValue(1.5)), json!(1.5)),
+            (Some(Kind::NumberValue(f64::NAN)), Value::Null),
+            (Some(Kind::NumberValue(f64::INFINITY)), Value::Null),
+            (Some(Kind::NullValue(0)), Value::Null),
+            (None, Value::Null),
+            (Some(Kind::BoolValue(true)), json!(true)),
+            (Some(Kind::StringValue("x".into())), json!("x")),
+        ];
+        for (kind, expected) in cases {
+            let json = from_prost(kind.clone());
+            assert_eq!(json, expected, "{kind:?}");
+            // Whole numbers deserialize into integer types again
+            if expected.is_i64() {
+                assert!(json.is_i64(), "{kind:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn nested_structs_and_lists_back_to_json() {
+        let doc = json!({
+            "name": "Ann",
+            "age": 41,
+            "score": 0.5,
+            "tags": ["a", null, 2, [true]],
+            "address": {"city": "Oslo", "geo": {"lat": 59.9, "zip": 150}},
+            "none": null,
+        });
+        let Value::Object(map) = doc.clone() else {
+            unreachable!()
+        };
+        assert_eq!(struct_to_json(to_struct(map, false)), doc);
+    }
+
+    fn schema(
+        id_field: Value,
+        fields: &[&str],
+    ) -> Result<CreateCollectionRequest> {
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|name| json!({"name": name, "type": "STRING"}))
+            .collect();
+        json_to_create_collection_request(json!({
+            "name": "people",
+            "document_id_field_name": id_field,
+            "fields": fields,
+        }))
+    }
+
+    #[test]
+    fn json_schema_leaves_an_empty_id_field_to_immudb() {
+        for id_field in [Value::Null, json!("")] {
+            let req = schema(id_field, &["name"]).unwrap();
+            assert_eq!(req.document_id_field_name, "");
+            assert_eq!(req.fields.len(), 1);
+        }
+        let err = schema(Value::Null, &["_id", "name"]).unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(m) if m.contains("'_id'")));
+    }
+
+    #[test]
+    fn json_schema_does_not_declare_an_explicit_id_field() {
+        let req = schema(json!("person_id"), &["name", "_id"]).unwrap();
+        assert_eq!(req.document_id_field_name, "person_id");
+        let names: Vec<_> =
+            req.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["name", "_id"]);
+        assert!(req.indexes.is_empty());
+
+        let err = schema(json!("person_id"), &["person_id"]).unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidInput(m) if m.contains("'person_id'"))
+        );
+    }
 }
@@ -1,3 +1,7 @@
+use std::collections::{HashMap, VecDeque};
+
+use uuid::Uuid;
+
 use crate::ImmuDB;
 use crate::error::Error;
 use crate::interceptor::SessionInterceptor;
@@ -6,6 +10,7 @@ use crate::model::{
     DeleteCollectionRequest, DocumentAtRevision, GetCollectionsRequest,
     GetCollectionsResponse, InsertDocumentsResponse, SearchDocumentsRequest,
 };
+use crate::retry::{RetryPolicy, with_retry};
 
 use super::Result;
 use super::protocol::model;
@@ -13,6 +18,90 @@ use super::protocol::model;
 pub mod builder;
 mod conv;
 
+/// Result of `insert_documents`: the transaction and the ids immudb
+/// assigned to the documents, in insertion order
+#[derive(Debug, Clone)]
+pub struct InsertedIds {
+    pub transaction_id: u64,
+    document_ids: Vec<String>,
+}
+
+impl InsertedIds {
+    pub fn ids(&self) -> &[String] {
+        &self.document_ids
+    }
+
+    /// Document ids parsed as UUIDs (immudb renders them as 32 hex chars)
+    pub fn uuids(&self) -> Result<Vec<Uuid>> {
+        self.document_ids
+            .iter()
+            .map(|id| {
+                Uuid::parse_str(id).map_err(|e| {
+                    Error::Decode(format!("invalid document id '{id}': {e}"))
+                })
+            })
+            .collect()
+    }
+}
+
+impl From<InsertDocumentsResponse> for InsertedIds {
+    fn from(resp: InsertDocumentsResponse) -> Self {
+        Self {
+            transaction_id: resp.transaction_id,
+            document_ids: resp.document_ids,
+        }
+    }
+}
+
+/// Document revision of a search result, with the document as JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocRecord {
+    pub revision: u64,
+    pub tx: u64,
+    pub document: serde_json::Value,
+}
+
+impl From<DocumentAtRevision> for DocRecord {
+    fn from(rev: DocumentAtRevision) -> Self {
+        Self {
+            revision: rev.revision,
+            tx: rev.transaction_id,
+            document: rev
+                .document
+                .map_or(serde_json::Value::Null, conv::struct_to_json),
+        }
+    }
+}
+
+/// Collection schema, as returned by [`DocClient::collections`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub document_id_field: String,
+    pub fields: Vec<FieldInfo>,
+    pub indexes: Vec<IndexInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInfo {
+    pub name: String,
+    pub field_type: builder::FieldType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexInfo {
+    pub fields: Vec<String>,
+    pub unique: bool,
+}
+
+/// Id field immudb creates when the collection doesn't name one
+const DEFAULT_ID_FIELD: &str = "_id";
+
+/// Query `limit` used when the search JSON doesn't specify one
+pub const DEFAULT_LIMIT: u32 = 100;
+/// Search `page_size` used when the builder doesn't specify one
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
 pub struct DocClient {
     inner: DocumentServiceClient<
         tonic::service::interceptor::InterceptedService<
@@ -20,51 +109,179 @@ pub struct DocClient {
             SessionInterceptor,
         >,
     >,
+    default_limit: u32,
+    default_page_size: u32,
+    large_ints_as_strings: bool,
+    retry: RetryPolicy,
+    validate_on_insert: bool,
+    schemas: HashMap<String, CollectionInfo>,
 }
 
 impl DocClient {
     pub(crate) fn new(db: &ImmuDB) -> Self {
         Self {
             inner: db.raw_doc(),
+            default_limit: DEFAULT_LIMIT,
+            default_page_size: DEFAULT_PAGE_SIZE,
+            large_ints_as_strings: false,
+            retry: db.retry_policy(),
+            validate_on_insert: false,
+            schemas: HashMap::new(),
         }
     }
 
-    pub async fn list_collections(&mut self) -> Result<Vec<model::Collection>> {
-        let GetCollectionsResponse { collections } = self
-            .inner
-            .get_collections(GetCollectionsRequest {})
+    /// Query `limit` applied when the search JSON omits `limit`
+    pub fn default_limit(mut self, limit: u32) -> Self {
+        self.default_limit = limit;
+        self
+    }
+
+    /// `page_size` applied when `SearchDocuments` doesn't set one
+    pub fn default_page_size(mut self, page_size: u32) -> Self {
+        self.default_page_size = page_size;
+        self
+    }
+
+    /// Document numbers are f64 on the wire, so integers beyond 2^53 lose
+    /// precision (a warning is logged). With this on, such integers are
+    /// inserted as strings and read back intact; query comparisons against
+    /// them must then use the string form too.
+    pub fn large_ints_as_strings(mut self, enabled: bool) -> Self {
+        self.large_ints_as_strings = enabled;
+        self
+    }
+
+    /// Check documents against the declared field types of their
+    /// collection before `insert_documents` sends them; fields the
+    /// collection doesn't declare are rejected. The schema is
+    /// fetched once per collection and cached; changes made through this
+    /// client refresh it, changes made elsewhere are not seen.
+    pub fn validate_on_insert(mut self, enabled: bool) -> Self {
+        self.validate_on_insert = enabled;
+        self
+    }
+
+    pub async fn list_collections(&self) -> Result<Vec<model::Collection>> {
+        let GetCollectionsResponse { collections } =
+            with_retry(&self.retry, || {
+                let mut cli = self.inner.clone();
+                async move { cli.get_collections(GetCollectionsRequest {}).await }
+            })
             .await?
             .into_inner();
         Ok(collections)
     }
 
+    /// Like [`DocClient::list_collections`], without the generated types
+    pub async fn collections(&self) -> Result<Vec<CollectionInfo>> {
+        self.list_collections()
+            .await?
+            .into_iter()
+            .map(CollectionInfo::try_from)
+            .collect()
+    }
+
     pub async fn create_collection(
         &mut self,
         param: builder::CreateCollection,
     ) -> Result<()> {
-        let mut fields: Vec<model::Field> = Vec::new();
-        let mut indexes: Vec<model::Index> = Vec::new();
-
-        for custom_field in param.fields.into_iter() {
-            let parts = conv::ProtobufFieldParts::from(custom_field);
-            fields.push(parts.proto_field);
-            if let Some(index) = parts.proto_index {
-                indexes.push(index);
-            }
+        let req = create_collection_request(param)?;
+        self.schemas.remove(&req.name);
+        self.inner.create_collection(req).await?;
+        Ok(())
+    }
+
+    /// Add `field` to an existing collection; an index is created as well
+    /// when the field is `indexed` or `unique`
+    pub async fn add_field(
+        &mut self,
+        collection: &str,
+        field: builder::Field,
+    ) -> Result<()> {
+        let existing = self.get_collection(collection).await?;
+        if existing.fields.iter().any(|f| f.name == field.name) {
+            return Err(Error::InvalidInput(format!(
+                "field '{}' already exists in collection '{collection}'",
+                field.name
+            )));
         }
 
-        let req = model::CreateCollectionRequest {
-            name: param.name,
-            document_id_field_name: param.document_id_field_name,
-            fields,
-            indexes,
-        };
+        self.schemas.remove(collection);
+        let parts = conv::ProtobufFieldParts::from(field);
+        self.inner
+            .add_field(model::AddFieldRequest {
+                collection_name: collection.into(),
+                field: Some(parts.proto_field),
+            })
+            .await?;
+        if let Some(index) = parts.proto_index {
+            self.inner
+                .create_index(model::CreateIndexRequest {
+                    collection_name: collection.into(),
+                    fields: index.fields,
+                    is_unique: index.is_unique,
+                })
+                .await?;
+        }
+        Ok(())
+    }
 
-        self.inner.create_collection(req).await?;
+    /// Create an index over existing `fields` of `collection`
+    pub async fn add_index(
+        &mut self,
+        collection: &str,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+        unique: bool,
+    ) -> Result<()> {
+        let fields: Vec<String> = fields.into_iter().map(Into::into).collect();
+        if fields.is_empty() {
+            return Err(Error::InvalidInput(
+                "index needs at least one field".into(),
+            ));
+        }
+        let existing = self.get_collection(collection).await?;
+        if let Some(missing) = fields
+            .iter()
+            .find(|name| !existing.fields.iter().any(|f| &f.name == *name))
+        {
+            return Err(Error::InvalidInput(format!(
+                "no field '{missing}' in collection '{collection}'"
+            )));
+        }
+
+        self.inner
+            .create_index(model::CreateIndexRequest {
+                collection_name: collection.into(),
+                fields,
+                is_unique: unique,
+            })
+            .await?;
         Ok(())
     }
 
+    async fn get_collection(&self, name: &str) -> Result<model::Collection> {
+        self.inner
+            .clone()
+            .get_collection(model::GetCollectionRequest { name: name.into() })
+            .await?
+            .into_inner()
+            .collection
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("no collection '{name}'"))
+            })
+    }
+
+    /// Cached schema of `collection`, fetched on first use
+    async fn schema(&mut self, collection: &str) -> Result<&CollectionInfo> {
+        if !self.schemas.contains_key(collection) {
+            let info = self.get_collection(collection).await?.try_into()?;
+            self.schemas.insert(collection.to_string(), info);
+        }
+        Ok(&self.schemas[collection])
+    }
+
     pub async fn delete_collection(&mut self, name: &str) -> Result<()> {
+        self.schemas.remove(name);
         self.inner
             .delete_collection(DeleteCollectionRequest { name: name.into() })
             .await?;
@@ -75,12 +292,23 @@ impl DocClient {
         &mut self,
         collection: &str,
         docs: Vec<serde_json::Value>,
-    ) -> Result<InsertDocumentsResponse> {
+    ) -> Result<InsertedIds> {
+        if self.validate_on_insert {
+            let schema = self.schema(collection).await?;
+            for (i, doc) in docs.iter().enumerate() {
+                validate_document(schema, doc).map_err(|e| {
+                    Error::InvalidInput(format!(
+                        "document {i} of '{collection}': {e}"
+                    ))
+                })?;
+            }
+        }
+
         let data = docs
             .into_iter()
             .map(|doc| {
                 if let serde_json::Value::Object(map) = doc {
-                    Ok(conv::to_struct(map))
+                    Ok(conv::to_struct(map, self.large_ints_as_strings))
                 } else {
                     Err("root of document must be a JSON object".to_string())
                 }
@@ -98,25 +326,810 @@ impl DocClient {
             .await?
             .into_inner();
 
-        Ok(result)
+        Ok(result.into())
     }
 
-    pub async fn search_document(
+    /// Best-effort insert reporting the outcome of every document, aligned
+    /// with `docs`. A failing batch is split in halves and retried down to
+    /// single documents, so one bad document costs about `2 * log2(n)`
+    /// extra round-trips; documents in batches that succeed are committed
+    /// in separate transactions.
+    pub async fn insert_documents_checked(
         &mut self,
+        collection: &str,
+        docs: Vec<serde_json::Value>,
+    ) -> Vec<Result<String>> {
+        let mut results: Vec<Option<Result<String>>> =
+            docs.iter().map(|_| None).collect();
+        let mut pending = VecDeque::from([(0, docs.len())]);
+
+        while let Some((start, end)) = pending.pop_front() {
+            if start == end {
+                continue;
+            }
+            let batch = docs[start..end].to_vec();
+            match self.insert_documents(collection, batch).await {
+                Ok(inserted) => {
+                    for (slot, id) in results[start..end]
+                        .iter_mut()
+                        .zip(inserted.document_ids)
+                    {
+                        *slot = Some(Ok(id));
+                    }
+                }
+                Err(e) if end - start == 1 => results[start] = Some(Err(e)),
+                Err(_) => {
+                    let mid = start + (end - start) / 2;
+                    pending.push_front((mid, end));
+                    pending.push_front((start, mid));
+                }
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|r| {
+                r.unwrap_or_else(|| {
+                    Err(Error::Unexpected("no document id returned".into()))
+                })
+            })
+            .collect()
+    }
+
+    /// Number of documents matching `query` (same JSON as for
+    /// [`builder::SearchDocuments`]; its `limit` is ignored)
+    pub async fn count_documents(
+        &self,
+        query: serde_json::Value,
+    ) -> Result<u64> {
+        let mut query =
+            conv::json_to_immudb_query(query, self.default_limit, true)?;
+        query.limit = 0;
+        let req = model::CountDocumentsRequest { query: Some(query) };
+        let count = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            let req = req.clone();
+            async move { cli.count_documents(req).await }
+        })
+        .await?
+        .into_inner()
+        .count;
+        u64::try_from(count).map_err(|_| {
+            Error::Decode(format!("negative document count {count}"))
+        })
+    }
+
+    pub async fn search_document(
+        &self,
         param: builder::SearchDocuments,
     ) -> Result<Vec<DocumentAtRevision>> {
-        let query = conv::json_to_immudb_query(param.query)?;
-        let model::SearchDocumentsResponse { revisions, .. } = self
-            .inner
-            .search_documents(SearchDocumentsRequest {
-                search_id: param.search_id,
-                query: Some(query),
-                page: param.page,
-                page_size: param.page_size,
-                keep_open: param.keep_open,
+        let query = match param.query {
+            builder::SearchQuery::Json(json) => conv::json_to_immudb_query(
+                json,
+                self.default_limit,
+                param.allow_unbounded,
+            )?,
+            builder::SearchQuery::Raw(query) => query,
+        };
+        let unbounded = query.limit == 0 && param.allow_unbounded;
+        let mut req = SearchDocumentsRequest {
+            search_id: param.search_id,
+            query: Some(query),
+            page: param.page,
+            page_size: param.page_size.unwrap_or(self.default_page_size),
+            keep_open: param.keep_open,
+        };
+        if !unbounded {
+            return self.search_page(&req).await;
+        }
+
+        // No limit: walk the pages until a short one
+        let mut all = Vec::new();
+        loop {
+            let revisions = self.search_page(&req).await?;
+            let done = revisions.len() < req.page_size as usize;
+            all.extend(revisions);
+            if done || req.page_size == 0 {
+                return Ok(all);
+            }
+            req.page += 1;
+        }
+    }
+
+    /// Like [`DocClient::search_document`], with the documents as JSON
+    pub async fn search(
+        &self,
+        param: builder::SearchDocuments,
+    ) -> Result<Vec<DocRecord>> {
+        let revisions = self.search_document(param).await?;
+        Ok(revisions.into_iter().map(DocRecord::from).collect())
+    }
+
+    /// Only the documents of [`DocClient::search`], as JSON objects
+    pub async fn search_as_json(
+        &self,
+        param: builder::SearchDocuments,
+    ) -> Result<Vec<serde_json::Value>> {
+        let records = self.search(param).await?;
+        Ok(records.into_iter().map(|r| r.document).collect())
+    }
+
+    async fn search_page(
+        &self,
+        req: &SearchDocumentsRequest,
+    ) -> Result<Vec<DocumentAtRevision>> {
+        let model::SearchDocumentsResponse { revisions, .. } =
+            with_retry(&self.retry, || {
+                let mut cli = self.inner.clone();
+                let req = req.clone();
+                async move { cli.search_documents(req).await }
             })
             .await?
             .into_inner();
         Ok(revisions)
     }
 }
+
+/// An empty id field name is sent as is and immudb names the id field
+/// `_id`. Either way immudb creates the id field itself, so a declared
+/// field of that name is rejected.
+fn create_collection_request(
+    param: builder::CreateCollection,
+) -> Result<model::CreateCollectionRequest> {
+    let id_field = match param.document_id_field_name.as_str() {
+        "" => DEFAULT_ID_FIELD,
+        name => name,
+    };
+    let mut fields: Vec<model::Field> = Vec::new();
+    let mut indexes: Vec<model::Index> = Vec::new();
+
+    for custom_field in param.fields.into_iter() {
+        if custom_field.name.is_empty() {
+            return Err(Error::InvalidInput(
+                "field name must not be empty".into(),
+            ));
+        }
+        if custom_field.name == id_field {
+            return Err(Error::InvalidInput(format!(
+                "field '{id_field}' is the document id, created by immudb"
+            )));
+        }
+        let parts = conv::ProtobufFieldParts::from(custom_field);
+        fields.push(parts.proto_field);
+        if let Some(index) = parts.proto_index {
+            indexes.push(index);
+        }
+    }
+
+    for index in param.indexes {
+        if index.fields.is_empty() {
+            return Err(Error::InvalidInput(
+                "index needs at least one field".into(),
+            ));
+        }
+        if let Some(missing) = index
+            .fields
+            .iter()
+            .find(|name| !fields.iter().any(|f| &f.name == *name))
+        {
+            return Err(Error::InvalidInput(format!(
+                "index over undeclared field '{missing}'"
+            )));
+        }
+        indexes.push(index);
+    }
+
+    Ok(model::CreateCollectionRequest {
+        name: param.name,
+        document_id_field_name: param.document_id_field_name,
+        fields,
+        indexes,
+    })
+}
+
+/// Check the declared fields present in `doc` (dotted names address
+/// nested objects) against their types. Fields the collection doesn't
+/// declare are rejected, except the document id field.
+fn validate_document(
+    schema: &CollectionInfo,
+    doc: &serde_json::Value,
+) -> std::result::Result<(), String> {
+    use builder::FieldType;
+    use serde_json::Value;
+
+    let Value::Object(root) = doc else {
+        return Err("root of document must be a JSON object".into());
+    };
+    check_declared(schema, "", root)?;
+    for field in &schema.fields {
+        let value = field
+            .name
+            .split('.')
+            .try_fold(doc, |value, part| value.get(part));
+        let Some(value) = value else { continue };
+        let ok = match (&field.field_type, value) {
+            (_, Value::Null) => true,
+            (FieldType::String, Value::String(_)) => true,
+            (FieldType::Boolean, Value::Bool(_)) => true,
+            (FieldType::Integer, Value::Number(n)) => n.is_i64() || n.is_u64(),
+            // large_ints_as_strings sends big integers as strings
+            (FieldType::Integer, Value::String(s)) => s.parse::<i128>().is_ok(),
+            (FieldType::Double, Value::Number(_)) => true,
+            (FieldType::Uuid, Value::String(s)) => Uuid::parse_str(s).is_ok(),
+            _ => false,
+        };
+        if !ok {
+            return Err(format!(
+                "field '{}' must be {}, got {value}",
+                field.name, field.field_type
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Every key of `object` (at dotted `prefix`) is a declared field, the id
+/// field, or an object holding declared nested fields
+fn check_declared(
+    schema: &CollectionInfo,
+    prefix: &str,
+    object: &serde_json::Map<String, serde_json::Value>,
+) -> std::result::Result<(), String> {
+    for (key, value) in object {
+        let name = format!("{prefix}{key}");
+        if name == schema.document_id_field
+            || schema.fields.iter().any(|f| f.name == name)
+        {
+            continue;
+        }
+        let nested = format!("{name}.");
+        match value {
+            serde_json::Value::Object(inner)
+                if schema
+                    .fields
+                    .iter()
+                    .any(|f| f.name.starts_with(&nested)) =>
+            {
+                check_declared(schema, &nested, inner)?;
+            }
+            _ => return Err(format!("undeclared field '{name}'")),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::builder::FieldType;
+    use super::*;
+    use crate::mock::Mock;
+
+    /// Full path of a `DocumentService` method, for [`Mock`]
+    pub(super) fn doc_method(method: &str) -> String {
+        format!("/immudb.model.DocumentService/{method}")
+    }
+
+    fn string_field(doc: &prost_types::Struct, field: &str) -> String {
+        match &doc.fields[field].kind {
+            Some(prost_types::value::Kind::StringValue(s)) => s.clone(),
+            other => panic!("'{field}' is not a string: {other:?}"),
+        }
+    }
+
+    fn schema() -> CollectionInfo {
+        let field = |name: &str, field_type| FieldInfo {
+            name: name.into(),
+            field_type,
+        };
+        CollectionInfo {
+            name: "people".into(),
+            document_id_field: "_id".into(),
+            fields: vec![
+                field("name", FieldType::String),
+                field("age", FieldType::Integer),
+                field("address.city", FieldType::String),
+            ],
+            indexes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn valid_documents() {
+        let schema = schema();
+        for doc in [
+            json!({}),
+            json!({"name": "Ann", "age": 41}),
+            json!({"_id": "abc", "name": null}),
+            json!({"address": {"city": "Oslo"}}),
+            json!({"age": "90071992547409930"}),
+        ] {
+            assert_eq!(validate_document(&schema, &doc), Ok(()), "{doc}");
+        }
+    }
+
+    #[test]
+    fn type_mismatch_names_the_field() {
+        let schema = schema();
+        let err = validate_document(&schema, &json!({"age": "old"}));
+        assert_eq!(
+            err.unwrap_err(),
+            "field 'age' must be INTEGER, got \"old\""
+        );
+        let err = validate_document(&schema, &json!({"age": 1.5}));
+        assert!(err.unwrap_err().starts_with("field 'age'"));
+        let err = validate_document(&schema, &json!({"address": {"city": 7}}));
+        assert!(err.unwrap_err().starts_with("field 'address.city'"));
+    }
+
+    #[test]
+    fn undeclared_field_names_the_field() {
+        let schema = schema();
+        let err =
+            validate_document(&schema, &json!({"name": "Ann", "nick": "A"}));
+        assert_eq!(err.unwrap_err(), "undeclared field 'nick'");
+        let err = validate_document(
+            &schema,
+            &json!({"address": {"city": "Oslo", "zip": "0150"}}),
+        );
+        assert_eq!(err.unwrap_err(), "undeclared field 'address.zip'");
+        // Only nested fields of `address` are declared
+        let err = validate_document(&schema, &json!({"address": "Oslo"}));
+        assert_eq!(err.unwrap_err(), "undeclared field 'address'");
+    }
+
+    fn create(
+        id_field: &str,
+        fields: &[&str],
+    ) -> Result<model::CreateCollectionRequest> {
+        create_collection_request(builder::CreateCollection {
+            name: "people".into(),
+            fields: fields
+                .iter()
+                .map(|name| builder::Field {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            indexes: Vec::new(),
+            document_id_field_name: id_field.into(),
+        })
+    }
+
+    #[test]
+    fn empty_id_field_is_left_to_immudb() {
+        let req = create("", &["name"]).unwrap();
+        assert_eq!(req.document_id_field_name, "");
+        assert_eq!(req.fields.len(), 1);
+
+        // immudb creates `_id` then
+        let err = create("", &["_id", "name"]).unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(m) if m.contains("'_id'")));
+    }
+
+    #[test]
+    fn explicit_id_field_is_not_declared() {
+        // immudb creates the id field from the name alone: declaring it
+        // too would make it a second field of the same name
+        let req = create("person_id", &["name"]).unwrap();
+        assert_eq!(req.document_id_field_name, "person_id");
+        assert!(req.fields.iter().all(|f| f.name != "person_id"));
+
+        let err = create("person_id", &["person_id"]).unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidInput(m) if m.contains("'person_id'"))
+        );
+        // `_id` is an ordinary name once another id field is chosen
+        assert!(create("person_id", &["_id"]).is_ok());
+    }
+
+    #[test]
+    fn empty_field_name_is_rejected() {
+        assert!(matches!(create("", &[""]), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn root_must_be_an_object() {
+        let err = validate_document(&schema(), &json!([1]));
+        assert_eq!(err.unwrap_err(), "root of document must be a JSON object");
+    }
+
+    #[tokio::test]
+    async fn checked_insert_isolates_the_failing_document() {
+        // Any batch containing `bad` fails; ids are the document names
+        let server = Mock::new()
+            .unary(
+                &doc_method("InsertDocuments"),
+                |req: model::InsertDocumentsRequest| {
+                    let names: Vec<_> = req
+                        .documents
+                        .iter()
+                        .map(|d| string_field(d, "name"))
+                        .collect();
+                    if names.iter().any(|n| n == "bad") {
+                        return Err(tonic::Status::already_exists("conflict"));
+                    }
+                    Ok(InsertDocumentsResponse {
+                        transaction_id: 1,
+                        document_ids: names,
+                    })
+                },
+            )
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let docs = vec![
+            json!({"name": "a"}),
+            json!({"name": "bad"}),
+            json!({"name": "c"}),
+        ];
+        let results = db.doc().insert_documents_checked("people", docs).await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), "a");
+        assert!(
+            matches!(&results[1], Err(Error::Protocol(s)) if s.code() == tonic::Code::AlreadyExists),
+            "{:?}",
+            results[1]
+        );
+        assert_eq!(results[2].as_ref().unwrap(), "c");
+
+        let batches: Vec<Vec<String>> = server
+            .calls_to(&doc_method("InsertDocuments"))
+            .iter()
+            .map(|c| {
+                c.decode::<model::InsertDocumentsRequest>()
+                    .documents
+                    .iter()
+                    .map(|d| string_field(d, "name"))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            batches,
+            [
+                vec!["a", "bad", "c"],
+                vec!["a"],
+                vec!["bad", "c"],
+                vec!["bad"],
+                vec!["c"]
+            ]
+        );
+    }
+
+    /// `people`: `name` (unique index) and `age`, id field `pid`
+    fn people_collection() -> model::Collection {
+        let field = |name: &str, ty: model::FieldType| model::Field {
+            name: name.into(),
+            r#type: ty as i32,
+        };
+        model::Collection {
+            name: "people".into(),
+            document_id_field_name: "pid".into(),
+            fields: vec![
+                field("name", model::FieldType::String),
+                field("age", model::FieldType::Integer),
+            ],
+            indexes: vec![model::Index {
+                fields: vec!["name".into()],
+                is_unique: true,
+            }],
+        }
+    }
+
+    fn collections_server() -> Mock {
+        Mock::new()
+            .unary(&doc_method("GetCollections"), |_: GetCollectionsRequest| {
+                Ok(GetCollectionsResponse {
+                    collections: vec![people_collection()],
+                })
+            })
+            .unary(
+                &doc_method("GetCollection"),
+                |req: model::GetCollectionRequest| {
+                    Ok(model::GetCollectionResponse {
+                        collection: (req.name == "people")
+                            .then(people_collection),
+                    })
+                },
+            )
+    }
+
+    #[tokio::test]
+    async fn collections_are_decoded() {
+        let server = collections_server().start().await;
+        let db = server.connect().await;
+
+        let collections = db.doc().collections().await.unwrap();
+        assert_eq!(
+            collections,
+            [CollectionInfo {
+                name: "people".into(),
+                document_id_field: "pid".into(),
+                fields: vec![
+                    FieldInfo {
+                        name: "name".into(),
+                        field_type: FieldType::String,
+                    },
+                    FieldInfo {
+                        name: "age".into(),
+                        field_type: FieldType::Integer,
+                    },
+                ],
+                indexes: vec![IndexInfo {
+                    fields: vec!["name".into()],
+                    unique: true,
+                }],
+            }]
+        );
+    }
+
+    fn alter_server() -> Mock {
+        collections_server()
+            .unary(&doc_method("AddField"), |_: model::AddFieldRequest| {
+                Ok(model::AddFieldResponse {})
+            })
+            .unary(
+                &doc_method("CreateIndex"),
+                |_: model::CreateIndexRequest| {
+                    Ok(model::CreateIndexResponse {})
+                },
+            )
+    }
+
+    #[tokio::test]
+    async fn add_field_sends_the_field_and_its_index() {
+        let server = alter_server().start().await;
+        let db = server.connect().await;
+        let mut doc = db.doc();
+
+        let email = builder::Field::name("email")
+            .field_type(FieldType::String)
+            .unique(true)
+            .build();
+        doc.add_field("people", email).await.unwrap();
+        let plain = builder::Field::name("score")
+            .field_type(FieldType::Double)
+            .build();
+        doc.add_field("people", plain).await.unwrap();
+
+        let added: Vec<_> = server
+            .calls_to(&doc_method("AddField"))
+            .iter()
+            .map(|c| c.decode::<model::AddFieldRequest>())
+            .collect();
+        assert_eq!(
+            added,
+            [
+                model::AddFieldRequest {
+                    collection_name: "people".into(),
+                    field: Some(model::Field {
+                        name: "email".into(),
+                        r#type: model::FieldType::String as i32,
+                    }),
+                },
+                model::AddFieldRequest {
+                    collection_name: "people".into(),
+                    field: Some(model::Field {
+                        name: "score".into(),
+                        r#type: model::FieldType::Double as i32,
+                    }),
+                },
+            ]
+        );
+        // Only the unique field gets an index
+        let indexes = server.calls_to(&doc_method("CreateIndex"));
+        assert_eq!(indexes.len(), 1);
+        assert_eq!(
+            indexes[0].decode::<model::CreateIndexRequest>(),
+            model::CreateIndexRequest {
+                collection_name: "people".into(),
+                fields: vec!["email".into()],
+                is_unique: true,
+            }
+        );
+
+        // Existing fields and unknown collections are caught up front
+        let name = builder::Field::name("name")
+            .field_type(FieldType::String)
+            .build();
+        let err = doc.add_field("people", name).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(m) if m.contains("'name'")));
+        let age = builder::Field::name("age")
+            .field_type(FieldType::String)
+            .build();
+        let err = doc.add_field("pets", age).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(m) if m.contains("'pets'")));
+        assert_eq!(server.calls_to(&doc_method("AddField")).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn add_index_checks_the_fields_exist() {
+        let server = alter_server().start().await;
+        let db = server.connect().await;
+        let mut doc = db.doc();
+
+        doc.add_index("people", ["age", "name"], false)
+            .await
+            .unwrap();
+        let calls = server.calls_to(&doc_method("CreateIndex"));
+        assert_eq!(
+            calls[0].decode::<model::CreateIndexRequest>(),
+            model::CreateIndexRequest {
+                collection_name: "people".into(),
+                fields: vec!["age".into(), "name".into()],
+                is_unique: false,
+            }
+        );
+
+        let err = doc.add_index("people", ["age", "nick"], true).await;
+        assert!(
+            matches!(err, Err(Error::InvalidInput(m)) if m.contains("'nick'"))
+        );
+        let err = doc.add_index("people", Vec::<String>::new(), true).await;
+        assert!(matches!(err, Err(Error::InvalidInput(_))));
+        assert_eq!(server.calls_to(&doc_method("CreateIndex")).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn inserted_ids_parse_as_uuids() {
+        let id = "0123456789abcdef0123456789abcdef";
+        let server = Mock::new()
+            .unary(
+                &doc_method("InsertDocuments"),
+                move |req: model::InsertDocumentsRequest| {
+                    Ok(InsertDocumentsResponse {
+                        transaction_id: 9,
+                        document_ids: req
+                            .documents
+                            .iter()
+                            .map(|d| match string_field(d, "name").as_str() {
+                                "ann" => id.into(),
+                                other => other.into(),
+                            })
+                            .collect(),
+                    })
+                },
+            )
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut doc = db.doc();
+
+        let inserted = doc
+            .insert_documents("people", vec![json!({"name": "ann"})])
+            .await
+            .unwrap();
+        assert_eq!(inserted.transaction_id, 9);
+        assert_eq!(inserted.ids(), [id]);
+        assert_eq!(inserted.uuids().unwrap(), [Uuid::parse_str(id).unwrap()]);
+
+        let inserted = doc
+            .insert_documents("people", vec![json!({"name": "not-a-uuid"})])
+            .await
+            .unwrap();
+        let err = inserted.uuids().unwrap_err();
+        assert!(
+            matches!(&err, Error::Decode(m) if m.contains("'not-a-uuid'")),
+            "{err:?}"
+        );
+    }
+
+    /// Answers every search with ann (revision 2, tx 7) and a revision
+    /// without a document
+    fn search_server() -> Mock {
+        Mock::new().unary(
+            &doc_method("SearchDocuments"),
+            |_: SearchDocumentsRequest| {
+                let ann = json!({"name": "ann", "age": 41, "tags": ["a"]});
+                let serde_json::Value::Object(ann) = ann else {
+                    unreachable!()
+                };
+                Ok(model::SearchDocumentsResponse {
+                    search_id: String::new(),
+                    revisions: vec![
+                        DocumentAtRevision {
+                            transaction_id: 7,
+                            revision: 2,
+                            document: Some(conv::to_struct(ann, false)),
+                            ..Default::default()
+                        },
+                        DocumentAtRevision {
+                            transaction_id: 8,
+                            revision: 1,
+                            ..Default::default()
+                        },
+                    ],
+                })
+            },
+        )
+    }
+
+    fn search(
+        query: serde_json::Value,
+        page_size: Option<u32>,
+    ) -> builder::SearchDocuments {
+        builder::SearchDocuments {
+            query: query.into(),
+            allow_unbounded: false,
+            search_id: String::new(),
+            page_size,
+            page: 1,
+            keep_open: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn search_returns_revisions_as_json() {
+        let server = search_server().start().await;
+        let db = server.connect().await;
+        let doc = db.doc();
+        let query = || search(json!({"collection_name": "people"}), None);
+
+        let records = doc.search(query()).await.unwrap();
+        assert_eq!(
+            records,
+            [
+                DocRecord {
+                    revision: 2,
+                    tx: 7,
+                    document: json!({"name": "ann", "age": 41, "tags": ["a"]}),
+                },
+                DocRecord {
+                    revision: 1,
+                    tx: 8,
+                    document: serde_json::Value::Null,
+                },
+            ]
+        );
+
+        let docs = doc.search_as_json(query()).await.unwrap();
+        assert_eq!(
+            docs,
+            [records[0].document.clone(), serde_json::Value::Null]
+        );
+    }
+
+    #[tokio::test]
+    async fn search_defaults_are_configurable() {
+        let server = search_server().start().await;
+        let db = server.connect().await;
+        let people = json!({"collection_name": "people"});
+
+        db.doc().search(search(people.clone(), None)).await.unwrap();
+        let doc = db.doc().default_limit(10).default_page_size(5);
+        doc.search(search(people, None)).await.unwrap();
+        let explicit = json!({"collection_name": "people", "limit": 3});
+        doc.search(search(explicit, Some(2))).await.unwrap();
+
+        let sent: Vec<(u32, u32)> = server
+            .calls_to(&doc_method("SearchDocuments"))
+            .iter()
+            .map(|c| {
+                let req = c.decode::<SearchDocumentsRequest>();
+                (req.query.unwrap().limit, req.page_size)
+            })
+            .collect();
+        assert_eq!(sent, [(DEFAULT_LIMIT, DEFAULT_PAGE_SIZE), (10, 5), (3, 2)]);
+    }
+
+    #[tokio::test]
+    async fn searches_share_one_client_concurrently() {
+        let delay = std::time::Duration::from_millis(300);
+        let server = search_server()
+            .delay(&doc_method("SearchDocuments"), delay)
+            .start()
+            .await;
+        let db = server.connect().await;
+        let doc = db.doc();
+        let people = || search(json!({"collection_name": "people"}), None);
+
+        // Both searches borrow `doc` and are in flight at the same time
+        let started = std::time::Instant::now();
+        let (a, b) = tokio::join!(doc.search(people()), doc.search(people()));
+        assert_eq!(a.unwrap(), b.unwrap());
+        assert!(started.elapsed() < delay * 2, "{:?}", started.elapsed());
+        assert_eq!(server.calls_to(&doc_method("SearchDocuments")).len(), 2);
+    }
+}
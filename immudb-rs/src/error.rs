@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use http::uri::InvalidUri;
 
 #[derive(thiserror::Error)]
@@ -7,7 +9,14 @@ pub enum Error {
     #[error("unexpected error: {0}")]
     Unexpected(String),
     #[error("protocol: {0}")]
-    Protocol(#[from] tonic::Status),
+    Protocol(tonic::Status),
+    /// No connection within `connect_timeout`
+    #[error("connect timed out after {0:?}")]
+    ConnectTimeout(Duration),
+    /// Deadline of the call passed, on the client (`request_timeout`) or
+    /// on the server
+    #[error("request timed out: {0}")]
+    RequestTimeout(tonic::Status),
     #[error("transport: {0}")]
     Transport(#[from] tonic::transport::Error),
     #[error("invalid input: {0}")]
@@ -16,6 +25,45 @@ pub enum Error {
     Decode(String),
     #[error("decode: {0}")]
     JsonDecode(#[from] serde_json::Error),
+    /// A proof returned by a verified call doesn't hold
+    #[error("verification failed: {0}")]
+    Tampered(String),
+    /// SQL rejected by immudb's parser; `position` is where it stopped
+    #[error("sql: {message}")]
+    Sql {
+        message: String,
+        position: Option<usize>,
+    },
+    #[cfg(feature = "rest")]
+    #[error("http: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+impl From<tonic::Status> for Error {
+    fn from(status: tonic::Status) -> Self {
+        if status.code() == tonic::Code::DeadlineExceeded
+            || is_client_deadline(&status)
+        {
+            Error::RequestTimeout(status)
+        } else {
+            Error::Protocol(status)
+        }
+    }
+}
+
+/// Deadline enforced by the channel itself, which tonic reports as
+/// CANCELLED
+fn is_client_deadline(status: &tonic::Status) -> bool {
+    use std::error::Error as _;
+    if status.code() != tonic::Code::Cancelled {
+        return false;
+    }
+    // tonic builds the status from its `TimeoutExpired` without keeping it
+    // as the source; its rendering is all that is left of the type
+    status
+        .source()
+        .is_some_and(|e| e.is::<tonic::TimeoutExpired>())
+        || status.message() == tonic::TimeoutExpired(()).to_string()
 }
 
 crate::impl_debug!(Error);
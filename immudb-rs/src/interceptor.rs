@@ -1,39 +1,159 @@
-use std::sync::{Arc, RwLock};
-use tonic::metadata::{Ascii, MetadataValue};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+use tonic::metadata::{Ascii, MetadataKey, MetadataValue};
 use tonic::service::Interceptor;
 
 use crate::error::Error;
 
+/// Metadata header names the session is sent under. The defaults are what
+/// immudb expects; override them for gateways that rename headers.
+#[derive(Debug, Clone)]
+pub struct HeaderNames {
+    pub session_id: String,
+    pub server_uuid: String,
+    pub authorization: String,
+}
+
+impl Default for HeaderNames {
+    fn default() -> Self {
+        Self {
+            session_id: "sessionid".into(),
+            server_uuid: "immudb-uuid".into(),
+            authorization: "authorization".into(),
+        }
+    }
+}
+
+struct HeaderKeys {
+    session_id: MetadataKey<Ascii>,
+    server_uuid: MetadataKey<Ascii>,
+    authorization: MetadataKey<Ascii>,
+}
+
+impl TryFrom<&HeaderNames> for HeaderKeys {
+    type Error = Error;
+    fn try_from(names: &HeaderNames) -> Result<Self, Self::Error> {
+        let key = |name: &str| {
+            MetadataKey::from_bytes(name.as_bytes()).map_err(|e| {
+                Error::InvalidInput(format!("header name '{name}': {e}"))
+            })
+        };
+        Ok(Self {
+            session_id: key(&names.session_id)?,
+            server_uuid: key(&names.server_uuid)?,
+            authorization: key(&names.authorization)?,
+        })
+    }
+}
+
 struct SessionState {
+    headers: HeaderKeys,
+    server_uuid_str: String,
+    session_id_str: String,
     server_uuid: MetadataValue<Ascii>,
     session_id: MetadataValue<Ascii>,
     db_token: RwLock<Option<MetadataValue<Ascii>>>,
+    /// Database the token was issued for, to re-issue it when it expires
+    database: RwLock<Option<String>>,
 }
 
+/// Adds the session headers (and the database token, once set) to every
+/// request of a client
 #[derive(Clone)]
 pub struct SessionInterceptor {
     state: Arc<SessionState>,
+    request_timeout: Option<Duration>,
 }
 
 impl SessionInterceptor {
-    pub fn new(session_id: &str, server_uuid: &str) -> Self {
-        let sid =
-            MetadataValue::try_from(session_id).expect("ascii session id");
-        let su =
-            MetadataValue::try_from(server_uuid).expect("ascii server uuid");
-        Self {
+    pub fn new(
+        session_id: &str,
+        server_uuid: &str,
+        headers: &HeaderNames,
+    ) -> crate::Result<Self> {
+        let value = |what: &str, v: &str| {
+            MetadataValue::try_from(v).map_err(|e| {
+                Error::InvalidInput(format!("ascii {what}: {e:?}"))
+            })
+        };
+        let sid = value("session id", session_id)?;
+        let su = value("server uuid", server_uuid)?;
+        Ok(Self {
             state: Arc::new(SessionState {
+                headers: HeaderKeys::try_from(headers)?,
+                server_uuid_str: server_uuid.to_string(),
+                session_id_str: session_id.to_string(),
                 server_uuid: su,
                 session_id: sid,
                 db_token: RwLock::new(None),
+                database: RwLock::new(None),
             }),
+            request_timeout: None,
+        })
+    }
+
+    /// Interceptor with the default header names and an optional token,
+    /// no `open_session` needed: for driving clients against mock servers
+    pub fn for_testing(
+        session_id: &str,
+        server_uuid: &str,
+        token: Option<&str>,
+    ) -> crate::Result<Self> {
+        let interceptor =
+            Self::new(session_id, server_uuid, &HeaderNames::default())?;
+        if let Some(token) = token {
+            interceptor.set_token(token.into())?;
         }
+        Ok(interceptor)
+    }
+
+    /// Deadline for requests that don't carry their own `grpc-timeout`
+    pub fn request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.state.session_id_str
+    }
+
+    pub fn server_uuid(&self) -> &str {
+        &self.state.server_uuid_str
+    }
+
+    /// Store the token issued by `use_database(database)`
+    pub fn set_database_token(
+        &self,
+        database: &str,
+        token: String,
+    ) -> crate::Result<()> {
+        self.set_token(token)?;
+        *self
+            .state
+            .database
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(database.into());
+        Ok(())
+    }
+
+    pub fn database(&self) -> Option<String> {
+        self.state
+            .database
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 
     pub fn set_token(&self, token: String) -> crate::Result<()> {
         let mv = MetadataValue::try_from(token)
             .map_err(|e| Error::InvalidInput(format!("ascii token: {e:?}")))?;
-        *self.state.db_token.write().unwrap() = Some(mv);
+        // The token is replaced wholesale, so a writer that panicked can't
+        // leave it half-updated: recover from poisoning instead of panicking
+        *self
+            .state
+            .db_token
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(mv);
         Ok(())
     }
 }
@@ -43,12 +163,68 @@ impl Interceptor for SessionInterceptor {
         &mut self,
         mut req: tonic::Request<()>,
     ) -> tonic::Result<tonic::Request<()>> {
+        if let Some(timeout) = self.request_timeout
+            && !req.metadata().contains_key("grpc-timeout")
+        {
+            req.set_timeout(timeout);
+        }
+        let headers = &self.state.headers;
         let md = req.metadata_mut();
-        md.insert("sessionid", self.state.session_id.clone());
-        md.insert("immudb-uuid", self.state.server_uuid.clone());
-        if let Some(tok) = self.state.db_token.read().unwrap().as_ref() {
-            md.insert("authorization", tok.clone()); // <— это важно
+        md.insert(headers.session_id.clone(), self.state.session_id.clone());
+        md.insert(headers.server_uuid.clone(), self.state.server_uuid.clone());
+        let token = self
+            .state
+            .db_token
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(tok) = token.as_ref() {
+            md.insert(headers.authorization.clone(), tok.clone()); // <— это важно
         }
         Ok(req)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_testing_sets_the_session_headers() {
+        let mut interceptor =
+            SessionInterceptor::for_testing("sid", "uuid", Some("tok"))
+                .unwrap();
+        let req = interceptor.call(tonic::Request::new(())).unwrap();
+        let md = req.metadata();
+        assert_eq!(md.get("sessionid").unwrap(), "sid");
+        assert_eq!(md.get("immudb-uuid").unwrap(), "uuid");
+        assert_eq!(md.get("authorization").unwrap(), "tok");
+
+        let mut interceptor =
+            SessionInterceptor::for_testing("sid", "uuid", None).unwrap();
+        let req = interceptor.call(tonic::Request::new(())).unwrap();
+        assert_eq!(req.metadata().get("sessionid").unwrap(), "sid");
+        assert!(req.metadata().get("authorization").is_none());
+    }
+
+    #[test]
+    fn poisoned_token_lock_is_recovered() {
+        let mut interceptor =
+            SessionInterceptor::for_testing("sid", "uuid", Some("old"))
+                .unwrap();
+        let state = interceptor.state.clone();
+        std::thread::spawn(move || {
+            let _guard = state.db_token.write().unwrap();
+            panic!("poison the token lock");
+        })
+        .join()
+        .unwrap_err();
+        assert!(interceptor.state.db_token.is_poisoned());
+
+        let req = interceptor.call(tonic::Request::new(())).unwrap();
+        assert_eq!(req.metadata().get("authorization").unwrap(), "old");
+
+        interceptor.set_token("new".into()).unwrap();
+        let req = interceptor.call(tonic::Request::new(())).unwrap();
+        assert_eq!(req.metadata().get("authorization").unwrap(), "new");
+    }
+}
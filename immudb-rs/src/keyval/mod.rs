@@ -0,0 +1,765 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tonic::Code;
+
+use crate::ImmuDB;
+use crate::interceptor::SessionInterceptor;
+use crate::retry::{RetryPolicy, with_retry};
+use crate::schema::immu_service_client::ImmuServiceClient;
+use crate::schema::{
+    Entries, Entry, HistoryRequest, KeyListRequest, KeyRequest, KeyValue,
+    ScanRequest, SetRequest, TxHeader, VerifiableGetRequest,
+    VerifiableSetRequest,
+};
+
+use super::Result;
+
+mod proof;
+mod state;
+
+pub use state::{MemoryStateStore, StateStore, VerificationState};
+
+/// One version of a key, as returned by [`KvClient::history`]
+#[derive(Debug, Clone)]
+pub struct KvEntry {
+    /// Transaction that wrote this version
+    pub tx: u64,
+    /// 1 for the first version of the key, 2 for the second, ...
+    pub revision: u64,
+    /// Empty for a deletion
+    pub value: Vec<u8>,
+    /// `true` if this version deletes the key
+    pub deleted: bool,
+}
+
+/// Entry read by [`KvClient::verified_get`], proven to be in the database
+#[derive(Debug, Clone)]
+pub struct VerifiedEntry {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    /// Transaction that wrote this version
+    pub tx: u64,
+    pub revision: u64,
+}
+
+/// Client: key-value API
+#[derive(Clone)]
+pub struct KvClient {
+    inner: ImmuServiceClient<
+        tonic::service::interceptor::InterceptedService<
+            tonic::transport::Channel,
+            SessionInterceptor,
+        >,
+    >,
+    retry: RetryPolicy,
+    session: SessionInterceptor,
+    state_store: Arc<dyn StateStore>,
+}
+
+impl KvClient {
+    pub(crate) fn new(db: &ImmuDB) -> Self {
+        Self {
+            inner: db.raw_main(),
+            retry: db.retry_policy(),
+            session: db.interceptor(),
+            state_store: db.state_store(),
+        }
+    }
+
+    /// Keys and values can be `&str`, `String`, `&[u8]`, `Vec<u8>`...
+    pub async fn set(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: impl AsRef<[u8]>,
+    ) -> Result<TxHeader> {
+        self.set_all(vec![(key.as_ref().to_vec(), value.as_ref().to_vec())])
+            .await
+    }
+
+    /// `None` if the key doesn't exist (or was deleted/expired)
+    pub async fn get(
+        &mut self,
+        key: impl AsRef<[u8]>,
+    ) -> Result<Option<Vec<u8>>> {
+        let req = KeyRequest {
+            key: key.as_ref().to_vec(),
+            ..Default::default()
+        };
+        let res = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            let req = req.clone();
+            async move { cli.get(req).await }
+        })
+        .await;
+        match res {
+            Ok(resp) => Ok(entry_value(resp.into_inner())),
+            Err(status) if is_key_not_found(&status) => Ok(None),
+            Err(status) => Err(status.into()),
+        }
+    }
+
+    /// Like [`KvClient::get`], also checking that the entry is in its
+    /// transaction and that this transaction is consistent with the last
+    /// verified state, which then advances (see [`StateStore`]). With no
+    /// verified state yet, the server's current state is trusted.
+    /// [`Error::Tampered`](crate::Error::Tampered) if a proof fails.
+    pub async fn verified_get(
+        &mut self,
+        key: impl AsRef<[u8]>,
+    ) -> Result<Option<VerifiedEntry>> {
+        let key = key.as_ref();
+        let state = self.verified_state().await?;
+        let req = VerifiableGetRequest {
+            key_request: Some(KeyRequest {
+                key: key.to_vec(),
+                ..Default::default()
+            }),
+            prove_since_tx: state.tx_id,
+        };
+        let res = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            let req = req.clone();
+            async move { cli.verifiable_get(req).await }
+        })
+        .await;
+        let ventry = match res {
+            Ok(resp) => resp.into_inner(),
+            Err(status) if is_key_not_found(&status) => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+
+        self.advance_state(proof::verify_entry(&ventry, key, &state)?);
+
+        let Some(entry) = ventry.entry else {
+            return Ok(None);
+        };
+        let (tx, revision) = (entry.tx, entry.revision);
+        Ok(entry_value(entry).map(|value| VerifiedEntry {
+            key: key.to_vec(),
+            value,
+            tx,
+            revision,
+        }))
+    }
+
+    /// Like [`KvClient::set`], also checking that the written entry is in
+    /// the committed transaction and that this transaction is consistent
+    /// with the last verified state, which then advances
+    pub async fn verified_set(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: impl AsRef<[u8]>,
+    ) -> Result<TxHeader> {
+        let (key, value) = (key.as_ref(), value.as_ref());
+        let state = self.verified_state().await?;
+        let req = VerifiableSetRequest {
+            set_request: Some(SetRequest {
+                k_vs: vec![KeyValue {
+                    key: key.to_vec(),
+                    value: value.to_vec(),
+                    metadata: None,
+                }],
+                ..Default::default()
+            }),
+            prove_since_tx: state.tx_id,
+        };
+        let vtx = if self.retry.retry_writes {
+            with_retry(&self.retry, || {
+                let mut cli = self.inner.clone();
+                let req = req.clone();
+                async move { cli.verifiable_set(req).await }
+            })
+            .await?
+        } else {
+            self.inner.verifiable_set(req).await?
+        }
+        .into_inner();
+
+        self.advance_state(proof::verify_tx(&vtx, key, value, &state)?);
+
+        Ok(vtx.tx.and_then(|tx| tx.header).unwrap_or_default())
+    }
+
+    /// Writes all entries in a single transaction (one round trip)
+    pub async fn set_all(
+        &mut self,
+        entries: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<TxHeader> {
+        let k_vs = entries
+            .into_iter()
+            .map(|(key, value)| KeyValue {
+                key,
+                value,
+                metadata: None,
+            })
+            .collect();
+        let req = SetRequest {
+            k_vs,
+            ..Default::default()
+        };
+        let header = if self.retry.retry_writes {
+            with_retry(&self.retry, || {
+                let mut cli = self.inner.clone();
+                let req = req.clone();
+                async move { cli.set(req).await }
+            })
+            .await?
+        } else {
+            self.inner.set(req).await?
+        }
+        .into_inner();
+        Ok(header)
+    }
+
+    /// Reads all keys in one round trip. The result is aligned with `keys`;
+    /// missing keys are `None`.
+    pub async fn get_all(
+        &mut self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>> {
+        let req = KeyListRequest {
+            keys: keys.clone(),
+            since_tx: 0,
+        };
+        let Entries { entries } = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            let req = req.clone();
+            async move { cli.get_all(req).await }
+        })
+        .await?
+        .into_inner();
+
+        let found: HashMap<Vec<u8>, Vec<u8>> = entries
+            .into_iter()
+            .filter_map(|e| {
+                let key = e.key.clone();
+                entry_value(e).map(|v| (key, v))
+            })
+            .collect();
+
+        // Not `remove`: a key listed twice gets its value both times
+        Ok(keys.iter().map(|k| found.get(k).cloned()).collect())
+    }
+
+    /// Keys starting with `prefix` (a byte-wise prefix match; an empty
+    /// prefix matches every key), in key order or reversed with `desc`.
+    pub async fn scan(
+        &mut self,
+        prefix: &[u8],
+        limit: u32,
+        desc: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.scan_from(prefix, None, limit, desc).await
+    }
+
+    /// Same as [`KvClient::scan`], continuing after `seek_key` (exclusive),
+    /// typically the last key of the previous page.
+    pub async fn scan_from(
+        &mut self,
+        prefix: &[u8],
+        seek_key: Option<&[u8]>,
+        limit: u32,
+        desc: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let req = ScanRequest {
+            prefix: prefix.to_vec(),
+            seek_key: seek_key.map(<[u8]>::to_vec).unwrap_or_default(),
+            limit: limit as u64,
+            desc,
+            ..Default::default()
+        };
+        let Entries { entries } = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            let req = req.clone();
+            async move { cli.scan(req).await }
+        })
+        .await?
+        .into_inner();
+
+        Ok(entries
+            .into_iter()
+            .filter_map(|e| {
+                let key = e.key.clone();
+                entry_value(e).map(|v| (key, v))
+            })
+            .collect())
+    }
+
+    /// Every version of `key`, oldest first (newest first with `desc`)
+    pub async fn history(
+        &mut self,
+        key: &[u8],
+        offset: u64,
+        limit: u32,
+        desc: bool,
+    ) -> Result<Vec<KvEntry>> {
+        let req = HistoryRequest {
+            key: key.to_vec(),
+            offset,
+            limit: i32::try_from(limit).unwrap_or(i32::MAX),
+            desc,
+            since_tx: 0,
+        };
+        let Entries { entries } = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            let req = req.clone();
+            async move { cli.history(req).await }
+        })
+        .await?
+        .into_inner();
+
+        Ok(entries
+            .into_iter()
+            .map(|e| KvEntry {
+                tx: e.tx,
+                revision: e.revision,
+                deleted: e.metadata.is_some_and(|m| m.deleted),
+                value: e.value,
+            })
+            .collect())
+    }
+}
+
+impl KvClient {
+    /// Last verified state of the current database, or the server's
+    /// current one (trusted on first use)
+    async fn verified_state(&mut self) -> Result<VerificationState> {
+        let database = self.session.database().unwrap_or_default();
+        if let Some(state) = self.state_store.get(&database) {
+            return Ok(state);
+        }
+        let current = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            async move { cli.current_state(()).await }
+        })
+        .await?
+        .into_inner();
+        Ok(VerificationState {
+            database,
+            tx_id: current.tx_id,
+            tx_hash: proof::proto_digest(&current.tx_hash)?,
+        })
+    }
+
+    /// Never moves the stored state back, e.g. after concurrent calls
+    fn advance_state(&self, state: VerificationState) {
+        let newer = self
+            .state_store
+            .get(&state.database)
+            .is_none_or(|current| current.tx_id < state.tx_id);
+        if newer {
+            self.state_store.set(state);
+        }
+    }
+}
+
+fn entry_value(entry: Entry) -> Option<Vec<u8>> {
+    let deleted = entry.metadata.as_ref().is_some_and(|m| m.deleted);
+    if deleted || entry.expired {
+        None
+    } else {
+        Some(entry.value)
+    }
+}
+
+pub(crate) fn is_key_not_found(status: &tonic::Status) -> bool {
+    status.code() == Code::NotFound
+        || status.message().contains("key not found")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::Mock;
+    use crate::schema::KvMetadata;
+
+    #[tokio::test]
+    async fn get_all_aligns_duplicate_and_missing_keys() {
+        let server = Mock::new()
+            .unary("GetAll", |req: KeyListRequest| {
+                let entries = req
+                    .keys
+                    .into_iter()
+                    .filter(|k| k.as_slice() != b"missing")
+                    .map(|key| Entry {
+                        tx: 1,
+                        value: [b"v-", key.as_slice()].concat(),
+                        key,
+                        ..Default::default()
+                    })
+                    .collect();
+                Ok(Entries { entries })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let keys = vec![b"a".to_vec(), b"missing".to_vec(), b"a".to_vec()];
+        let values = db.kv().get_all(keys.clone()).await.unwrap();
+        assert_eq!(
+            values,
+            vec![Some(b"v-a".to_vec()), None, Some(b"v-a".to_vec())]
+        );
+
+        let sent: KeyListRequest = server.calls_to("GetAll")[0].decode();
+        assert_eq!(sent.keys, keys);
+    }
+
+    #[tokio::test]
+    async fn scan_sends_the_request_and_keeps_the_order() {
+        let server = Mock::new()
+            .unary("Scan", |req: ScanRequest| {
+                let mut keys = vec![b"user:1", b"user:2", b"user:3"];
+                if req.desc {
+                    keys.reverse();
+                }
+                let entries = keys
+                    .into_iter()
+                    .map(|key| Entry {
+                        key: key.to_vec(),
+                        value: [b"v-", &key[..]].concat(),
+                        ..Default::default()
+                    })
+                    .collect();
+                Ok(Entries { entries })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let keys = |entries: Vec<(Vec<u8>, Vec<u8>)>| -> Vec<Vec<u8>> {
+            entries
+                .into_iter()
+                .map(|(k, v)| {
+                    assert_eq!(v, [b"v-", &k[..]].concat());
+                    k
+                })
+                .collect()
+        };
+        let asc = db.kv().scan(b"user:", 10, false).await.unwrap();
+        assert_eq!(keys(asc), [b"user:1", b"user:2", b"user:3"]);
+        let desc = db
+            .kv()
+            .scan_from(b"user:", Some(b"user:9"), 3, true)
+            .await
+            .unwrap();
+        assert_eq!(keys(desc), [b"user:3", b"user:2", b"user:1"]);
+
+        let sent: Vec<ScanRequest> =
+            server.calls_to("Scan").iter().map(|c| c.decode()).collect();
+        assert_eq!(sent[0].prefix, b"user:");
+        assert!(sent[0].seek_key.is_empty());
+        assert_eq!((sent[0].limit, sent[0].desc), (10, false));
+        assert_eq!(sent[1].seek_key, b"user:9");
+        assert_eq!((sent[1].limit, sent[1].desc), (3, true));
+    }
+
+    #[tokio::test]
+    async fn history_decodes_every_version() {
+        let server = Mock::new()
+            .unary("History", |req: HistoryRequest| {
+                let version = |tx: u64, value: &[u8], deleted: bool| Entry {
+                    tx,
+                    key: req.key.clone(),
+                    value: value.to_vec(),
+                    revision: tx - 10,
+                    metadata: deleted.then(|| KvMetadata {
+                        deleted: true,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                };
+                Ok(Entries {
+                    entries: vec![
+                        version(11, b"v1", false),
+                        version(12, b"v2", false),
+                        version(13, b"", true),
+                    ],
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let history = db.kv().history(b"k", 0, 10, false).await.unwrap();
+        let versions: Vec<_> = history
+            .iter()
+            .map(|e| (e.tx, e.revision, e.value.as_slice(), e.deleted))
+            .collect();
+        assert_eq!(
+            versions,
+            [
+                (11, 1, &b"v1"[..], false),
+                (12, 2, &b"v2"[..], false),
+                (13, 3, &b""[..], true),
+            ]
+        );
+
+        let sent: HistoryRequest = server.calls_to("History")[0].decode();
+        assert_eq!(sent.key, b"k");
+        assert_eq!(sent.limit, 10);
+        assert!(!sent.desc);
+    }
+
+    #[tokio::test]
+    async fn set_all_writes_one_transaction() {
+        let server = Mock::new()
+            .unary("Set", |req: SetRequest| {
+                Ok(TxHeader {
+                    id: 5,
+                    nentries: req.k_vs.len() as i32,
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let entries = vec![
+            (b"a".to_vec(), b"1".to_vec()),
+            (b"b".to_vec(), b"2".to_vec()),
+        ];
+        let header = db.kv().set_all(entries.clone()).await.unwrap();
+        assert_eq!((header.id, header.nentries), (5, 2));
+
+        let calls = server.calls_to("Set");
+        assert_eq!(calls.len(), 1);
+        let sent: Vec<_> = calls[0]
+            .decode::<SetRequest>()
+            .k_vs
+            .into_iter()
+            .map(|kv| (kv.key, kv.value))
+            .collect();
+        assert_eq!(sent, entries);
+    }
+
+    #[tokio::test]
+    async fn set_and_get_accept_str_string_and_byte_keys() {
+        let server = Mock::new()
+            .unary("Set", |_: SetRequest| {
+                Ok(TxHeader {
+                    id: 1,
+                    ..Default::default()
+                })
+            })
+            .unary("Get", |req: KeyRequest| {
+                Ok(Entry {
+                    value: [b"v-", req.key.as_slice()].concat(),
+                    key: req.key,
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut kv = db.kv();
+
+        kv.set("key", b"v".to_vec()).await.unwrap();
+        kv.set(String::from("key"), b"v".to_vec()).await.unwrap();
+        kv.set(&b"key"[..], b"v".to_vec()).await.unwrap();
+        assert_eq!(kv.get("key").await.unwrap(), Some(b"v-key".to_vec()));
+        assert_eq!(
+            kv.get(String::from("key")).await.unwrap(),
+            Some(b"v-key".to_vec())
+        );
+        assert_eq!(kv.get(&b"key"[..]).await.unwrap(), Some(b"v-key".to_vec()));
+
+        let set: Vec<_> = server
+            .calls_to("Set")
+            .iter()
+            .map(|c| {
+                let kvs = c.decode::<SetRequest>().k_vs;
+                assert_eq!(kvs.len(), 1);
+                (kvs[0].key.clone(), kvs[0].value.clone())
+            })
+            .collect();
+        assert_eq!(set, vec![(b"key".to_vec(), b"v".to_vec()); 3]);
+        let get: Vec<_> = server
+            .calls_to("Get")
+            .iter()
+            .map(|c| c.decode::<KeyRequest>().key)
+            .collect();
+        assert_eq!(get, vec![b"key".to_vec(); 3]);
+    }
+
+    #[tokio::test]
+    async fn get_of_a_missing_key_is_none() {
+        let server = Mock::new()
+            .unary("Get", |req: KeyRequest| -> std::result::Result<Entry, _> {
+                match req.key.as_slice() {
+                    b"not-found" => Err(tonic::Status::not_found("no entry")),
+                    // What immudb actually sends for a missing key
+                    b"unknown" => Err(tonic::Status::unknown("key not found")),
+                    _ => Err(tonic::Status::internal("disk full")),
+                }
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut kv = db.kv();
+
+        assert_eq!(kv.get("not-found").await.unwrap(), None);
+        assert_eq!(kv.get("unknown").await.unwrap(), None);
+        assert!(kv.get("other").await.is_err());
+    }
+
+    mod verified {
+        use std::sync::Arc;
+
+        use super::*;
+        use crate::Error;
+        use crate::keyval::proof::tests::{Ledger, flip, key, ledger, value};
+        use crate::schema::{ImmutableState, VerifiableEntry, VerifiableTx};
+
+        /// `n` in `key{n}`
+        fn tx_of(key: &[u8]) -> u64 {
+            std::str::from_utf8(&key[3..]).unwrap().parse().unwrap()
+        }
+
+        /// Serves the ledger: the entry of the requested key, proven since
+        /// the requested tx, and a current state at `current`
+        fn serve(l: &Arc<Ledger>, current: u64) -> Mock {
+            let (l, state) = (l.clone(), l.clone());
+            Mock::new()
+                .unary("VerifiableGet", move |req: VerifiableGetRequest| {
+                    let key = req.key_request.unwrap().key;
+                    Ok(l.verifiable_entry(tx_of(&key), req.prove_since_tx))
+                })
+                .unary("CurrentState", move |_: ()| {
+                    Ok(ImmutableState {
+                        db: "defaultdb".into(),
+                        tx_id: current,
+                        tx_hash: state.alh(current).to_vec(),
+                        ..Default::default()
+                    })
+                })
+        }
+
+        fn proven_since(server: &crate::mock::MockServer) -> Vec<u64> {
+            server
+                .calls_to("VerifiableGet")
+                .iter()
+                .map(|c| c.decode::<VerifiableGetRequest>().prove_since_tx)
+                .collect()
+        }
+
+        #[tokio::test]
+        async fn verified_get_proves_since_the_stored_state() {
+            let l = Arc::new(ledger(9));
+            let server = serve(&l, 9).start().await;
+            let db = server.connect().await;
+            let store = db.state_store();
+            store.set(l.state(3));
+            let mut kv = db.kv();
+
+            let entry = kv.verified_get(key(7)).await.unwrap().unwrap();
+            assert_eq!((entry.key, entry.value), (key(7), value(7)));
+            assert_eq!(entry.tx, 7);
+            assert_eq!(store.get("defaultdb"), Some(l.state(7)));
+
+            // Older than the state: proven since it, which stays put
+            let entry = kv.verified_get(key(5)).await.unwrap().unwrap();
+            assert_eq!((entry.value, entry.tx), (value(5), 5));
+            assert_eq!(store.get("defaultdb"), Some(l.state(7)));
+
+            assert_eq!(proven_since(&server), [3, 7]);
+            assert!(server.calls_to("CurrentState").is_empty());
+        }
+
+        #[tokio::test]
+        async fn verified_get_trusts_the_current_state_on_first_use() {
+            let l = Arc::new(ledger(9));
+            let server = serve(&l, 4).start().await;
+            let db = server.connect().await;
+
+            let entry = db.kv().verified_get(key(6)).await.unwrap().unwrap();
+            assert_eq!((entry.value, entry.tx), (value(6), 6));
+            assert_eq!(db.state_store().get("defaultdb"), Some(l.state(6)));
+            assert_eq!(proven_since(&server), [4]);
+            assert_eq!(server.calls_to("CurrentState").len(), 1);
+        }
+
+        #[tokio::test]
+        async fn verified_get_of_a_missing_key_is_none() {
+            let l = Arc::new(ledger(9));
+            let server = Mock::new()
+                .unary("VerifiableGet", |_: VerifiableGetRequest| {
+                    Err::<VerifiableEntry, _>(tonic::Status::unknown(
+                        "key not found",
+                    ))
+                })
+                .start()
+                .await;
+            let db = server.connect().await;
+            db.state_store().set(l.state(3));
+
+            assert!(db.kv().verified_get(key(5)).await.unwrap().is_none());
+            assert_eq!(db.state_store().get("defaultdb"), Some(l.state(3)));
+        }
+
+        #[tokio::test]
+        async fn verified_set_advances_the_state() {
+            let l = Arc::new(ledger(9));
+            let vtx = l.verifiable_tx(2, 8);
+            let server = serve(&l, 2)
+                .unary("VerifiableSet", move |_: VerifiableSetRequest| {
+                    Ok(vtx.clone())
+                })
+                .start()
+                .await;
+            let db = server.connect().await;
+
+            let header = db.kv().verified_set(key(8), value(8)).await.unwrap();
+            assert_eq!(&header, l.header(8));
+            assert_eq!(db.state_store().get("defaultdb"), Some(l.state(8)));
+
+            let sent: VerifiableSetRequest =
+                server.calls_to("VerifiableSet")[0].decode();
+            assert_eq!(sent.prove_since_tx, 2);
+            let kv = &sent.set_request.unwrap().k_vs[0];
+            assert_eq!((&kv.key, &kv.value), (&key(8), &value(8)));
+        }
+
+        #[tokio::test]
+        async fn tampered_proofs_leave_the_state_unchanged() {
+            let l = Arc::new(ledger(9));
+            let mut vtx = l.verifiable_tx(3, 8);
+            flip(&mut vtx.tx.as_mut().unwrap().entries[0].h_value);
+            let mut ventry = l.verifiable_entry(7, 3);
+            flip(&mut ventry.entry.as_mut().unwrap().value);
+            let server = Mock::new()
+                .unary("VerifiableSet", move |_: VerifiableSetRequest| {
+                    Ok::<VerifiableTx, _>(vtx.clone())
+                })
+                .unary("VerifiableGet", move |_: VerifiableGetRequest| {
+                    Ok(ventry.clone())
+                })
+                .start()
+                .await;
+            let db = server.connect().await;
+            db.state_store().set(l.state(3));
+            let mut kv = db.kv();
+
+            let res = kv.verified_set(key(8), value(8)).await;
+            assert!(matches!(res, Err(Error::Tampered(_))), "{res:?}");
+            let res = kv.verified_get(key(7)).await;
+            assert!(matches!(res, Err(Error::Tampered(_))), "{res:?}");
+            assert_eq!(db.state_store().get("defaultdb"), Some(l.state(3)));
+        }
+
+        #[tokio::test]
+        async fn advance_state_never_moves_back() {
+            let l = ledger(9);
+            let server = Mock::new().start().await;
+            let db = server.connect().await;
+            let kv = db.kv();
+
+            kv.advance_state(l.state(8));
+            kv.advance_state(l.state(3));
+            assert_eq!(db.state_store().get("defaultdb"), Some(l.state(8)));
+            kv.advance_state(l.state(9));
+            assert_eq!(db.state_store().get("defaultdb"), Some(l.state(9)));
+        }
+    }
+}
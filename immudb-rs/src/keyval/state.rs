@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// Last verified state of a database: a transaction and its accumulated
+/// linear hash (Alh). Verified calls prove consistency with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationState {
+    pub database: String,
+    pub tx_id: u64,
+    pub tx_hash: [u8; 32],
+}
+
+/// Where verified calls keep the last verified state of each database.
+/// Persist it (file, kv store, ...) to detect tampering across restarts;
+/// use one store per immudb server.
+pub trait StateStore: Send + Sync + std::fmt::Debug {
+    fn get(&self, database: &str) -> Option<VerificationState>;
+    fn set(&self, state: VerificationState);
+}
+
+/// Default [`StateStore`]: lost when the process exits
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    states: Mutex<HashMap<String, VerificationState>>,
+}
+
+impl StateStore for MemoryStateStore {
+    fn get(&self, database: &str) -> Option<VerificationState> {
+        self.states
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(database)
+            .cloned()
+    }
+
+    fn set(&self, state: VerificationState) {
+        self.states
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(state.database.clone(), state);
+    }
+}
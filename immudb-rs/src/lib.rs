@@ -1,18 +1,40 @@
+pub use client::Compression;
 pub use client::ImmuDB;
+pub use error::Error;
+pub use interceptor::HeaderNames;
+pub use interceptor::SessionInterceptor;
 pub use protocol::model;
 pub use protocol::schema;
-pub use to_params_derive::ToParams;
+pub use protocol::schema::TxMode;
+pub use retry::RetryPolicy;
+pub use sql::Transaction;
+pub use to_params_derive::{FromRow, ToParams};
 
 mod client;
 mod error;
 mod interceptor;
+#[cfg(test)]
+mod mock;
 mod protocol;
+mod retry;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod document;
 pub mod keyval;
+#[cfg(feature = "rest")]
+pub mod rest;
 pub mod sql;
+#[cfg(feature = "testing")]
+pub mod testing;
 
-pub type Result<T> = std::result::Result<T, error::Error>;
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Clients are shared across tasks (e.g. in axum/tower handlers)
+static_assertions::assert_impl_all!(ImmuDB: Send, Sync);
+static_assertions::assert_impl_all!(sql::SqlClient: Send, Sync);
+static_assertions::assert_impl_all!(document::DocClient: Send, Sync);
+static_assertions::assert_impl_all!(keyval::KvClient: Send, Sync);
 
 pub fn error_chain_fmt(
     e: &impl std::error::Error,
@@ -66,7 +66,7 @@ async fn main() -> Result<()> {
     let docs = SearchDocuments::query(search_json)
         .page(1)
         .page_size(10)
-        .execute(&mut doc)
+        .execute(&doc)
         .await?;
 
     println!("{docs:#?}");
@@ -0,0 +1,305 @@
+//! In-process gRPC server standing in for immudb in unit tests
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use http::{HeaderMap, Request, Response};
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::body::Incoming;
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use prost::Message;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tonic::{Code, Status};
+
+use crate::ImmuDB;
+use crate::schema::{OpenSessionResponse, UseDatabaseReply};
+
+type Body = UnsyncBoxBody<Bytes, Infallible>;
+type Handler =
+    Arc<dyn Fn(Bytes) -> Result<Vec<Bytes>, Status> + Send + Sync + 'static>;
+
+pub(crate) const SESSION_ID: &str = "mock-session";
+pub(crate) const SERVER_UUID: &str = "mock-uuid";
+
+/// A request the server received
+#[derive(Debug, Clone)]
+pub(crate) struct Call {
+    pub path: String,
+    pub headers: HeaderMap,
+    /// First message of the request, unframed
+    pub body: Bytes,
+}
+
+impl Call {
+    pub fn decode<M: Message + Default>(&self) -> M {
+        M::decode(self.body.clone()).expect("mock: undecodable request")
+    }
+}
+
+#[derive(Clone)]
+struct Route {
+    handler: Handler,
+    delay: Option<Duration>,
+    /// Ends the stream after the messages instead of OK
+    fail_with: Option<(Code, String)>,
+}
+
+/// Routes by method: `"SQLExec"` is an `ImmuService` method, a name
+/// starting with `/` is a full path (e.g. for `DocumentService`).
+/// Session calls (`OpenSession`, `UseDatabase`, ...) are answered by
+/// default; unknown methods get UNIMPLEMENTED.
+#[derive(Clone)]
+pub(crate) struct Mock {
+    routes: HashMap<String, Route>,
+    /// Status for gzip-compressed requests; `None` decompresses them
+    reject_compressed: Option<Code>,
+}
+
+fn path(method: &str) -> String {
+    if method.starts_with('/') {
+        method.to_string()
+    } else {
+        format!("/immudb.schema.ImmuService/{method}")
+    }
+}
+
+impl Mock {
+    pub fn new() -> Self {
+        Self {
+            routes: HashMap::new(),
+            reject_compressed: None,
+        }
+        .unary("OpenSession", |_: ()| {
+            Ok(OpenSessionResponse {
+                session_id: SESSION_ID.into(),
+                server_uuid: SERVER_UUID.into(),
+            })
+        })
+        .unary("UseDatabase", |db: crate::schema::Database| {
+            Ok(UseDatabaseReply {
+                token: format!("token-{}", db.database_name),
+            })
+        })
+        .unary("CloseSession", |_: ()| Ok(()))
+        .unary("KeepAlive", |_: ()| Ok(()))
+    }
+
+    pub fn unary<Req, Resp>(
+        self,
+        method: &str,
+        f: impl Fn(Req) -> Result<Resp, Status> + Send + Sync + 'static,
+    ) -> Self
+    where
+        Req: Message + Default,
+        Resp: Message,
+    {
+        self.stream(method, move |req| f(req).map(|resp| vec![resp]))
+    }
+
+    /// Server-streaming method: every message of the `Vec` is sent
+    pub fn stream<Req, Resp>(
+        mut self,
+        method: &str,
+        f: impl Fn(Req) -> Result<Vec<Resp>, Status> + Send + Sync + 'static,
+    ) -> Self
+    where
+        Req: Message + Default,
+        Resp: Message,
+    {
+        let handler: Handler = Arc::new(move |body| {
+            let req = Req::decode(body)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            Ok(f(req)?
+                .iter()
+                .map(|resp| Bytes::from(resp.encode_to_vec()))
+                .collect())
+        });
+        self.routes.insert(
+            path(method),
+            Route {
+                handler,
+                delay: None,
+                fail_with: None,
+            },
+        );
+        self
+    }
+
+    /// Fail every compressed request with `code`, as a server without
+    /// support for the encoding does with UNIMPLEMENTED
+    pub fn reject_compressed(mut self, code: Code) -> Self {
+        self.reject_compressed = Some(code);
+        self
+    }
+
+    /// Answer `method` only after `delay`
+    pub fn delay(mut self, method: &str, delay: Duration) -> Self {
+        self.routes
+            .get_mut(&path(method))
+            .expect("mock: delay of an unknown method")
+            .delay = Some(delay);
+        self
+    }
+
+    /// End every response stream of `method` with `status` once its
+    /// messages are sent, as a query failing midway does
+    pub fn fail_stream(mut self, method: &str, status: Status) -> Self {
+        self.routes
+            .get_mut(&path(method))
+            .expect("mock: fail_stream of an unknown method")
+            .fail_with = Some((status.code(), status.message().to_string()));
+        self
+    }
+
+    pub async fn start(self) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let reject_compressed = self.reject_compressed;
+        let routes = Arc::new(self.routes);
+        let recorded = calls.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let routes = routes.clone();
+                let calls = recorded.clone();
+                let service = service_fn(move |req| {
+                    serve(routes.clone(), calls.clone(), reject_compressed, req)
+                });
+                tokio::spawn(
+                    hyper::server::conn::http2::Builder::new(
+                        TokioExecutor::new(),
+                    )
+                    .serve_connection(TokioIo::new(stream), service),
+                );
+            }
+        });
+        MockServer { addr, calls, task }
+    }
+}
+
+async fn serve(
+    routes: Arc<HashMap<String, Route>>,
+    calls: Arc<Mutex<Vec<Call>>>,
+    reject_compressed: Option<Code>,
+    req: Request<Incoming>,
+) -> Result<Response<Body>, Infallible> {
+    let path = req.uri().path().to_string();
+    let headers = req.headers().clone();
+    let mut framed = match req.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) => return Ok(error(Status::internal(e.to_string()))),
+    };
+    // Compression flag and length of the first message
+    let (compressed, mut body) = if framed.len() >= 5 {
+        let compressed = framed.get_u8() == 1;
+        let len = framed.get_u32() as usize;
+        (compressed, framed.split_to(len.min(framed.len())))
+    } else {
+        (false, Bytes::new())
+    };
+    if compressed && reject_compressed.is_none() {
+        let mut plain = Vec::new();
+        if let Err(e) =
+            flate2::read::GzDecoder::new(&body[..]).read_to_end(&mut plain)
+        {
+            return Ok(error(Status::internal(e.to_string())));
+        }
+        body = plain.into();
+    }
+    calls.lock().unwrap().push(Call {
+        path: path.clone(),
+        headers,
+        body: body.clone(),
+    });
+
+    if let Some(code) = reject_compressed.filter(|_| compressed) {
+        return Ok(error(Status::new(code, "compressed request")));
+    }
+    let Some(route) = routes.get(&path) else {
+        return Ok(error(Status::unimplemented(path)));
+    };
+    if let Some(delay) = route.delay {
+        tokio::time::sleep(delay).await;
+    }
+    let end = match &route.fail_with {
+        Some((code, message)) => Status::new(*code, message.clone()),
+        None => Status::ok(""),
+    };
+    Ok(match (route.handler)(body) {
+        Ok(messages) => success(messages, end),
+        Err(status) => error(status),
+    })
+}
+
+/// `messages`, then `end` in the trailers
+fn success(messages: Vec<Bytes>, end: Status) -> Response<Body> {
+    let mut buf = BytesMut::new();
+    for msg in messages {
+        buf.put_u8(0);
+        buf.put_u32(msg.len() as u32);
+        buf.put(msg);
+    }
+    let mut trailers = HeaderMap::new();
+    end.add_header(&mut trailers).expect("mock: invalid status");
+    let body = Full::new(buf.freeze())
+        .with_trailers(async { Some(Ok(trailers)) })
+        .boxed_unsync();
+    Response::builder()
+        .header("content-type", "application/grpc")
+        .body(body)
+        .unwrap()
+}
+
+/// Trailers-only response
+fn error(status: Status) -> Response<Body> {
+    let (parts, ()) = status.into_http::<()>().into_parts();
+    Response::from_parts(parts, Empty::new().boxed_unsync())
+}
+
+pub(crate) struct MockServer {
+    addr: SocketAddr,
+    calls: Arc<Mutex<Vec<Call>>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub fn uri(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Session without the keepalive task
+    pub async fn connect(&self) -> ImmuDB {
+        ImmuDB::builder()
+            .app_keepalive(false)
+            .connect(self.uri())
+            .await
+            .unwrap()
+    }
+
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Calls made to `method` (named as in [`Mock::unary`])
+    pub fn calls_to(&self, method: &str) -> Vec<Call> {
+        let path = path(method);
+        self.calls()
+            .into_iter()
+            .filter(|c| c.path == path)
+            .collect()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
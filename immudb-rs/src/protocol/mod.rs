@@ -1,8 +1,15 @@
+// Generated by build.rs; only the lints the generated code trips are allowed
+
+// Only referenced by the options of the proto files
+#[allow(dead_code)]
 #[path = "grpc.gateway.protoc_gen_openapiv2.options.rs"]
 mod gateway;
+#[allow(dead_code)]
 #[path = "google.api.rs"]
 mod google_api;
 #[path = "immudb.model.rs"]
 pub mod model;
+// Stream types of the camelCase `stream*`/`exportTx` rpcs
+#[allow(non_camel_case_types)]
 #[path = "immudb.schema.rs"]
 pub mod schema;
@@ -0,0 +1,531 @@
+//! Minimal client for immudb's REST gateway, for environments where only
+//! the gateway is reachable. Speaks the gateway's JSON mapping of the gRPC
+//! messages, so it shares [`Params`] and [`QueryResult`] with the gRPC
+//! client.
+
+use base64::Engine;
+use base64::prelude::{BASE64_STANDARD, BASE64_URL_SAFE};
+use bon::Builder;
+use serde_json::{Value as JsonValue, json};
+
+use crate::Result;
+use crate::error::Error;
+use crate::keyval::is_key_not_found;
+use crate::schema::{SqlQueryResult, SqlValue, sql_value};
+use crate::sql::{Params, QueryResult};
+
+#[derive(Debug, Clone, Builder)]
+#[builder(start_fn = builder, finish_fn(vis = "", name = build_internal))]
+pub struct RestOptions {
+    /// Gateway root including the api prefix, e.g. "http://localhost:8080/api"
+    #[builder(start_fn, into)]
+    pub base_url: String,
+
+    #[builder(into, default = String::from("immudb"))]
+    pub username: String,
+
+    #[builder(into, default = String::from("immudb"))]
+    pub password: String,
+
+    #[builder(into, default = String::from("defaultdb"))]
+    pub database: String,
+}
+
+impl<State: rest_options_builder::IsComplete> RestOptionsBuilder<State> {
+    /// Log in and select the database
+    pub async fn connect(self) -> Result<RestClient> {
+        let opts = self.build_internal();
+        let mut client = RestClient {
+            http: reqwest::Client::new(),
+            base_url: opts.base_url.trim_end_matches('/').to_string(),
+            token: None,
+        };
+
+        let resp = client
+            .post(
+                "/login",
+                &json!({
+                    "user": BASE64_STANDARD.encode(opts.username),
+                    "password": BASE64_STANDARD.encode(opts.password),
+                }),
+            )
+            .await?;
+        client.token = Some(json_str(&resp, "token")?);
+
+        let resp = client
+            .get_json(&format!("/db/use/{}", path_segment(&opts.database)))
+            .await?
+            .unwrap_or_default();
+        client.token = Some(json_str(&resp, "token")?);
+
+        Ok(client)
+    }
+}
+
+/// REST gateway client: `set`/`get` and SQL queries
+#[derive(Debug, Clone)]
+pub struct RestClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl RestClient {
+    pub fn builder(base_url: impl Into<String>) -> RestOptionsBuilder {
+        RestOptions::builder(base_url)
+    }
+
+    /// Returns the id of the committed transaction
+    pub async fn set(&self, key: &[u8], value: &[u8]) -> Result<u64> {
+        let resp = self
+            .post(
+                "/db/set",
+                &json!({
+                    "KVs": [{
+                        "key": BASE64_STANDARD.encode(key),
+                        "value": BASE64_STANDARD.encode(value),
+                    }]
+                }),
+            )
+            .await?;
+        json_u64(&resp["id"])
+    }
+
+    /// `None` if the key does not exist
+    pub async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let path = format!("/db/get/{}", BASE64_URL_SAFE.encode(key));
+        // immudb reports a missing key as UNKNOWN "key not found", which
+        // the gateway answers with a 500 rather than a 404
+        let resp = match self.get_json(&path).await {
+            Ok(Some(resp)) => resp,
+            Ok(None) => return Ok(None),
+            Err(Error::Protocol(status)) if is_key_not_found(&status) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        match resp.get("value").and_then(JsonValue::as_str) {
+            Some(value) => Ok(Some(decode_b64(value)?)),
+            // Empty values are omitted from the JSON
+            None => Ok(Some(Vec::new())),
+        }
+    }
+
+    /// SELECT over the gateway's unary `sqlquery` endpoint
+    pub async fn query<P>(
+        &self,
+        sql: impl Into<String>,
+        params: P,
+    ) -> Result<QueryResult>
+    where
+        P: Into<Params>,
+    {
+        let params: Vec<JsonValue> = params
+            .into()
+            .into_inner()
+            .into_iter()
+            .map(|p| {
+                json!({
+                    "name": p.name,
+                    "value": p.value.map(sql_value_to_gateway),
+                })
+            })
+            .collect();
+        let resp = self
+            .post(
+                "/db/sqlquery",
+                &json!({ "sql": sql.into(), "params": params }),
+            )
+            .await?;
+        Ok(sql_query_result_from_gateway(&resp)?.into())
+    }
+
+    async fn post(&self, path: &str, body: &JsonValue) -> Result<JsonValue> {
+        let req = self.http.post(self.url(path)).json(body);
+        let resp = self.authorize(req).send().await?;
+        check_status(resp).await
+    }
+
+    /// GET; a 404 becomes `None`
+    async fn get_json(&self, path: &str) -> Result<Option<JsonValue>> {
+        let req = self.http.get(self.url(path));
+        let resp = self.authorize(req).send().await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        check_status(resp).await.map(Some)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    fn authorize(
+        &self,
+        req: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => req.bearer_auth(token),
+            None => req,
+        }
+    }
+}
+
+/// Gateway errors carry the gRPC code: surface them as `Error::Protocol`
+async fn check_status(resp: reqwest::Response) -> Result<JsonValue> {
+    let status = resp.status();
+    let body: JsonValue = resp.json().await.unwrap_or_default();
+    if status.is_success() {
+        return Ok(body);
+    }
+    let code = body
+        .get("code")
+        .and_then(JsonValue::as_i64)
+        .map(|c| tonic::Code::from_i32(c as i32))
+        .unwrap_or(tonic::Code::Unknown);
+    let message = body
+        .get("message")
+        .and_then(JsonValue::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| status.to_string());
+    Err(Error::Protocol(tonic::Status::new(code, message)))
+}
+
+fn json_str(v: &JsonValue, field: &str) -> Result<String> {
+    v.get(field)
+        .and_then(JsonValue::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| Error::Decode(format!("gateway reply has no '{field}'")))
+}
+
+/// int64 fields are JSON strings in the gateway mapping
+fn json_i64(v: &JsonValue) -> Result<i64> {
+    match v {
+        JsonValue::String(s) => s.parse().map_err(|_| {
+            Error::Decode(format!("expected int64 string, got '{s}'"))
+        }),
+        JsonValue::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| Error::Decode(format!("expected int64, got {n}"))),
+        // Zero values are omitted
+        JsonValue::Null => Ok(0),
+        other => Err(Error::Decode(format!("expected int64, got {other}"))),
+    }
+}
+
+fn json_u64(v: &JsonValue) -> Result<u64> {
+    let n = json_i64(v)?;
+    u64::try_from(n).map_err(|_| Error::Decode(format!("negative id {n}")))
+}
+
+/// Percent-encode `s` as a single URL path segment
+fn path_segment(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~' => char::from(b).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn decode_b64(s: &str) -> Result<Vec<u8>> {
+    BASE64_STANDARD
+        .decode(s)
+        .or_else(|_| BASE64_URL_SAFE.decode(s))
+        .map_err(|e| Error::Decode(format!("invalid base64: {e}")))
+}
+
+fn sql_value_to_gateway(v: SqlValue) -> JsonValue {
+    use sql_value::Value::*;
+    match v.value {
+        None | Some(Null(_)) => json!({ "null": null }),
+        Some(N(n)) => json!({ "n": n.to_string() }),
+        Some(S(s)) => json!({ "s": s }),
+        Some(B(b)) => json!({ "b": b }),
+        Some(Bs(bs)) => json!({ "bs": BASE64_STANDARD.encode(bs) }),
+        Some(Ts(ts)) => json!({ "ts": ts.to_string() }),
+        Some(F(f)) => json!({ "f": f }),
+    }
+}
+
+fn sql_value_from_gateway(v: &JsonValue) -> Result<SqlValue> {
+    use sql_value::Value::*;
+    let value = if let Some(n) = v.get("n") {
+        N(json_i64(n)?)
+    } else if let Some(s) = v.get("s").and_then(JsonValue::as_str) {
+        S(s.to_string())
+    } else if let Some(b) = v.get("b").and_then(JsonValue::as_bool) {
+        B(b)
+    } else if let Some(bs) = v.get("bs").and_then(JsonValue::as_str) {
+        Bs(decode_b64(bs)?)
+    } else if let Some(ts) = v.get("ts") {
+        Ts(json_i64(ts)?)
+    } else if let Some(f) = v.get("f").and_then(JsonValue::as_f64) {
+        F(f)
+    } else {
+        Null(0)
+    };
+    Ok(SqlValue { value: Some(value) })
+}
+
+fn sql_query_result_from_gateway(v: &JsonValue) -> Result<SqlQueryResult> {
+    use crate::schema::{Column, Row};
+
+    let array = |v: &JsonValue, field: &str| -> Vec<JsonValue> {
+        v.get(field)
+            .and_then(JsonValue::as_array)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let string = |v: &JsonValue, field: &str| -> String {
+        v.get(field)
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let columns = array(v, "columns")
+        .iter()
+        .map(|c| Column {
+            name: string(c, "name"),
+            r#type: string(c, "type"),
+        })
+        .collect();
+    let rows = array(v, "rows")
+        .iter()
+        .map(|r| {
+            Ok(Row {
+                columns: array(r, "columns")
+                    .iter()
+                    .filter_map(|c| c.as_str().map(str::to_string))
+                    .collect(),
+                values: array(r, "values")
+                    .iter()
+                    .map(sql_value_from_gateway)
+                    .collect::<Result<_>>()?,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(SqlQueryResult { columns, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use http::{Request, Response, StatusCode};
+    use http_body_util::{BodyExt, Full};
+    use hyper::body::Incoming;
+    use hyper::service::service_fn;
+    use hyper_util::rt::TokioIo;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Method, path, bearer token and JSON body of a gateway request
+    type Recorded = (String, String, Option<String>, JsonValue);
+
+    /// HTTP/1 stand-in for the gateway: logs in as `immudb`, knows the
+    /// key `k` (value `v`) and answers every `sqlquery` with two rows
+    async fn gateway() -> (String, Arc<Mutex<Vec<Recorded>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let calls = recorded.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let calls = calls.clone();
+                let service = service_fn(move |req| answer(calls.clone(), req));
+                tokio::spawn(
+                    hyper::server::conn::http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service),
+                );
+            }
+        });
+        (format!("http://{addr}/api"), recorded)
+    }
+
+    async fn answer(
+        calls: Arc<Mutex<Vec<Recorded>>>,
+        req: Request<Incoming>,
+    ) -> std::result::Result<Response<Full<Bytes>>, Infallible> {
+        let method = req.method().to_string();
+        let path = req.uri().path().to_string();
+        let token = req
+            .headers()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_string);
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        let body = serde_json::from_slice(&body).unwrap_or(JsonValue::Null);
+        calls
+            .lock()
+            .unwrap()
+            .push((method, path.clone(), token, body.clone()));
+
+        let b64 = |s: &str| BASE64_STANDARD.encode(s);
+        let (status, reply) = match path.as_str() {
+            "/api/login" if body["user"] == b64("immudb") => {
+                (StatusCode::OK, json!({ "token": "login-token" }))
+            }
+            "/api/login" => (
+                StatusCode::UNAUTHORIZED,
+                json!({ "code": 16, "message": "invalid user name or password" }),
+            ),
+            p if p.starts_with("/api/db/use/") => {
+                (StatusCode::OK, json!({ "token": "db-token" }))
+            }
+            "/api/db/set" => (StatusCode::OK, json!({ "id": "42" })),
+            p if p
+                == format!("/api/db/get/{}", BASE64_URL_SAFE.encode("k")) =>
+            {
+                (
+                    StatusCode::OK,
+                    json!({ "key": b64("k"), "value": b64("v") }),
+                )
+            }
+            // What immudb sends: UNKNOWN, which the gateway maps to a 500
+            p if p.starts_with("/api/db/get/") => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!({ "code": 2, "message": "key not found", "details": [] }),
+            ),
+            "/api/db/sqlquery" => (
+                StatusCode::OK,
+                json!({
+                    "columns": [
+                        { "name": "(users.id)", "type": "INTEGER" },
+                        { "name": "(users.name)", "type": "VARCHAR" },
+                        { "name": "(users.avatar)", "type": "BLOB" },
+                    ],
+                    "rows": [
+                        {
+                            "columns": ["(users.id)", "(users.name)", "(users.avatar)"],
+                            "values": [{ "n": "1" }, { "s": "ann" }, { "bs": b64("png") }],
+                        },
+                        {
+                            "columns": ["(users.id)", "(users.name)", "(users.avatar)"],
+                            "values": [{ "n": "2" }, { "s": "bob" }, { "null": null }],
+                        },
+                    ],
+                }),
+            ),
+            _ => (StatusCode::NOT_FOUND, JsonValue::Null),
+        };
+        let mut resp = Response::new(Full::new(Bytes::from(reply.to_string())));
+        *resp.status_mut() = status;
+        Ok(resp)
+    }
+
+    #[tokio::test]
+    async fn connect_logs_in_and_selects_the_database() {
+        let (url, calls) = gateway().await;
+        RestClient::builder(url.as_str())
+            .database("my db")
+            .connect()
+            .await
+            .unwrap();
+
+        let calls = calls.lock().unwrap();
+        let (method, path, token, body) = &calls[0];
+        assert_eq!((method.as_str(), path.as_str()), ("POST", "/api/login"));
+        assert_eq!(*token, None);
+        assert_eq!(body["password"], BASE64_STANDARD.encode("immudb"));
+        let (method, path, token, _) = &calls[1];
+        assert_eq!(
+            (method.as_str(), path.as_str()),
+            ("GET", "/api/db/use/my%20db")
+        );
+        assert_eq!(token.as_deref(), Some("login-token"));
+    }
+
+    #[tokio::test]
+    async fn rejected_login_is_a_protocol_error() {
+        let (url, _) = gateway().await;
+        let err = RestClient::builder(url.as_str())
+            .username("nobody")
+            .connect()
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::Protocol(s) if s.code() == tonic::Code::Unauthenticated),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn set_and_get() {
+        let (url, calls) = gateway().await;
+        let client = RestClient::builder(url).connect().await.unwrap();
+
+        assert_eq!(client.set(b"k", b"v").await.unwrap(), 42);
+        assert_eq!(client.get(b"k").await.unwrap(), Some(b"v".to_vec()));
+        assert_eq!(client.get(b"missing").await.unwrap(), None);
+
+        let calls = calls.lock().unwrap();
+        let (_, path, token, body) = &calls[2];
+        assert_eq!(path, "/api/db/set");
+        assert_eq!(token.as_deref(), Some("db-token"));
+        assert_eq!(
+            body["KVs"][0],
+            json!({
+                "key": BASE64_STANDARD.encode("k"),
+                "value": BASE64_STANDARD.encode("v"),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn query_converts_params_and_rows() {
+        let (url, calls) = gateway().await;
+        let client = RestClient::builder(url).connect().await.unwrap();
+
+        let params = Params::new()
+            .bind("id", 1)
+            .bind("name", "ann")
+            .bind("avatar", b"png".to_vec())
+            .bind_null("deleted_at");
+        let res = client
+            .query("SELECT * FROM users WHERE id >= @id", params)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(
+            res.row_as_json(0).unwrap(),
+            json!({ "id": 1, "name": "ann", "avatar": BASE64_STANDARD.encode("png") })
+        );
+        assert_eq!(
+            res.row_as_json(1).unwrap(),
+            json!({ "id": 2, "name": "bob", "avatar": null })
+        );
+
+        let calls = calls.lock().unwrap();
+        let (_, path, _, body) = &calls[2];
+        assert_eq!(path, "/api/db/sqlquery");
+        assert_eq!(body["sql"], "SELECT * FROM users WHERE id >= @id");
+        assert_eq!(
+            body["params"],
+            json!([
+                { "name": "id", "value": { "n": "1" } },
+                { "name": "name", "value": { "s": "ann" } },
+                { "name": "avatar", "value": { "bs": BASE64_STANDARD.encode("png") } },
+                { "name": "deleted_at", "value": { "null": null } },
+            ])
+        );
+    }
+
+    #[test]
+    fn path_segments_are_percent_encoded() {
+        assert_eq!(path_segment("defaultdb"), "defaultdb");
+        assert_eq!(path_segment("a b/c?"), "a%20b%2Fc%3F");
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use tonic::{Code, Status};
+
+/// Retry policy for transient gRPC failures (`Unavailable`,
+/// `ResourceExhausted`), e.g. during a server failover.
+///
+/// Applied to idempotent calls only (queries, reads); writes are retried
+/// only with `retry_writes`, since a write may have been applied before
+/// the connection dropped.
+#[derive(Debug, Clone, Copy, bon::Builder)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying
+    #[builder(default = 3)]
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on every next one
+    #[builder(default = Duration::from_millis(100))]
+    pub base_backoff: Duration,
+    #[builder(default = false)]
+    pub retry_writes: bool,
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            base_backoff: Duration::ZERO,
+            retry_writes: false,
+        }
+    }
+
+    /// Exponential backoff with "full jitter": uniform in [0, base * 2^n]
+    fn backoff(&self, attempt: u32) -> Duration {
+        let cap = self
+            .base_backoff
+            .saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX));
+        let random = RandomState::new().build_hasher().finish();
+        cap.mul_f64((random % 1_000) as f64 / 1_000.0)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+fn is_transient(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::ResourceExhausted)
+}
+
+/// Runs `f` until it succeeds, fails with a non-transient status or the
+/// policy runs out of retries.
+pub(crate) async fn with_retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    mut f: F,
+) -> std::result::Result<T, Status>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, Status>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Err(status)
+                if attempt < policy.max_retries && is_transient(&status) =>
+            {
+                let delay = policy.backoff(attempt);
+                tracing::debug!(
+                    %status,
+                    attempt,
+                    ?delay,
+                    "transient immudb error, retrying"
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::ImmuDB;
+    use crate::mock::{Mock, MockServer};
+    use crate::schema::{SqlExecRequest, SqlExecResult, SqlQueryRequest};
+    use crate::sql::Params;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::builder()
+            .max_retries(3)
+            .base_backoff(Duration::from_millis(1))
+            .build()
+    }
+
+    /// `SQLQuery` and `SQLExec` are unavailable for the first `failures`
+    /// calls each
+    async fn flaky_server(failures: u32) -> MockServer {
+        let (queries, execs) =
+            (Arc::new(AtomicU32::new(0)), Arc::new(AtomicU32::new(0)));
+        Mock::new()
+            .stream("SQLQuery", move |_: SqlQueryRequest| {
+                if queries.fetch_add(1, Ordering::SeqCst) < failures {
+                    return Err(Status::unavailable("failover"));
+                }
+                Ok(vec![crate::schema::SqlQueryResult::default()])
+            })
+            .unary("SQLExec", move |_: SqlExecRequest| {
+                if execs.fetch_add(1, Ordering::SeqCst) < failures {
+                    return Err(Status::unavailable("failover"));
+                }
+                Ok(SqlExecResult::default())
+            })
+            .start()
+            .await
+    }
+
+    async fn connect(server: &MockServer, policy: RetryPolicy) -> ImmuDB {
+        ImmuDB::builder()
+            .app_keepalive(false)
+            .retry(policy)
+            .connect(server.uri())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_succeed_after_transient_failures() {
+        let server = flaky_server(3).await;
+        let db = connect(&server, policy()).await;
+
+        db.sql().query("SELECT 1", Params::new()).await.unwrap();
+        assert_eq!(server.calls_to("SQLQuery").len(), 4);
+    }
+
+    #[tokio::test]
+    async fn reads_give_up_after_the_limit() {
+        let server = flaky_server(4).await;
+        let db = connect(&server, policy()).await;
+
+        let err = db.sql().query("SELECT 1", Params::new()).await.unwrap_err();
+        assert!(
+            matches!(&err, crate::error::Error::Protocol(s) if s.code() == Code::Unavailable),
+            "{err:?}"
+        );
+        assert_eq!(server.calls_to("SQLQuery").len(), 4);
+    }
+
+    #[tokio::test]
+    async fn writes_are_retried_only_with_retry_writes() {
+        let server = flaky_server(1).await;
+        let db = connect(&server, policy()).await;
+        db.sql()
+            .exec("DELETE FROM t", Params::new())
+            .await
+            .unwrap_err();
+        assert_eq!(server.calls_to("SQLExec").len(), 1);
+
+        let server = flaky_server(1).await;
+        let policy = RetryPolicy {
+            retry_writes: true,
+            ..policy()
+        };
+        let db = connect(&server, policy).await;
+        db.sql().exec("DELETE FROM t", Params::new()).await.unwrap();
+        assert_eq!(server.calls_to("SQLExec").len(), 2);
+    }
+
+    #[test]
+    fn backoff_stays_under_the_doubled_cap() {
+        let policy = policy();
+        for attempt in 0..5 {
+            assert!(
+                policy.backoff(attempt) <= Duration::from_millis(1 << attempt)
+            );
+        }
+        assert_eq!(RetryPolicy::disabled().backoff(3), Duration::ZERO);
+    }
+}
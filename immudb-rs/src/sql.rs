@@ -1,12 +1,16 @@
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
 use serde::de::DeserializeOwned;
-use serde_json::{Map as JsonMap, Value as JsonValue};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use serde_json::Value as JsonValue;
 use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::pin::Pin;
-use time::{OffsetDateTime, UtcOffset};
+use std::sync::Arc;
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
 use tonic::metadata::{Ascii, MetadataValue};
-use tonic::{Request, Status};
+use tonic::{Request, Streaming};
 use uuid::Uuid;
 
 use crate::Result;
@@ -14,10 +18,14 @@ use crate::client::ImmuDB;
 use crate::error::Error;
 use crate::interceptor::SessionInterceptor;
 use crate::protocol::schema::{
-    NamedParam, SqlExecRequest, SqlExecResult, SqlQueryRequest, SqlValue,
-    immu_service_client::ImmuServiceClient, sql_value,
+    CommittedSqlTx, NamedParam, SqlExecRequest, SqlExecResult, SqlQueryRequest,
+    SqlQueryResult, SqlValue, Table, immu_service_client::ImmuServiceClient,
+    sql_value,
+};
+use crate::retry::{RetryPolicy, with_retry};
+use crate::schema::{
+    EntriesSpec, NewTxRequest, NewTxResponse, TxHeader, TxMode, TxRequest,
 };
-use crate::schema::{NewTxRequest, NewTxResponse, TxMode};
 
 type BoxFut<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
 
@@ -25,6 +33,13 @@ pub trait ToParams {
     fn to_params(&self) -> crate::sql::Params;
 }
 
+/// Build a value from one result row; `#[derive(FromRow)]` matches
+/// columns to fields by name, honoring `#[sql(rename = "...")]` like
+/// `ToParams` does, so a struct round-trips through both derives.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row, cols: &[Column]) -> crate::Result<Self>;
+}
+
 impl<T: ToParams + ?Sized> From<&T> for Params {
     fn from(t: &T) -> Self {
         t.to_params()
@@ -38,9 +53,15 @@ pub enum SqlArg<'a> {
     I64(i64),
     F64(f64),
     Bool(bool),
+    /// VARCHAR (UTF-8)
     Str(Cow<'a, str>),
+    /// BLOB; immudb doesn't coerce bytes to VARCHAR, so text held as bytes
+    /// must go through [`Params::bind_utf8`] / [`Params::bind_str_lossy`]
     Bytes(Cow<'a, [u8]>),
     Ts(i64),
+    /// immudb has no JSON SQL type: scalars bind as their SQL counterparts,
+    /// objects and arrays as their serialized text (use a VARCHAR column)
+    Json(JsonValue),
 }
 
 #[macro_export]
@@ -65,12 +86,12 @@ macro_rules! impl_from_for_sqlarg_borrowed {
     };
 }
 
-impl_from_for_sqlarg!(i64, |n| SqlArg::I64(n));
+impl_from_for_sqlarg!(i64, SqlArg::I64);
 impl_from_for_sqlarg!(i32, |n| SqlArg::I64(n as i64));
 impl_from_for_sqlarg!(i16, |n| SqlArg::I64(n as i64));
 impl_from_for_sqlarg!(i8, |n| SqlArg::I64(n as i64));
-impl_from_for_sqlarg!(bool, |b| SqlArg::Bool(b));
-impl_from_for_sqlarg!(f64, |f| SqlArg::F64(f));
+impl_from_for_sqlarg!(bool, SqlArg::Bool);
+impl_from_for_sqlarg!(f64, SqlArg::F64);
 impl_from_for_sqlarg!(String, |s| SqlArg::Str(Cow::Owned(s)));
 impl_from_for_sqlarg!(Vec<u8>, |b| SqlArg::Bytes(Cow::Owned(b)));
 impl_from_for_sqlarg!(Uuid, |u: Uuid| SqlArg::Bytes(Cow::Owned(
@@ -81,6 +102,7 @@ impl_from_for_sqlarg!(time::OffsetDateTime, |dt: OffsetDateTime| {
     let micros = dt_utc.unix_timestamp_nanos() / 1_000;
     SqlArg::Ts(micros as i64)
 });
+impl_from_for_sqlarg!(JsonValue, SqlArg::Json);
 impl_from_for_sqlarg!(u8, |n| SqlArg::I64(n as i64));
 impl_from_for_sqlarg!(u16, |n| SqlArg::I64(n as i64));
 impl_from_for_sqlarg!(u32, |n| SqlArg::I64(n as i64));
@@ -88,6 +110,27 @@ impl_from_for_sqlarg!(u64, |n| SqlArg::I64(n as i64));
 
 impl_from_for_sqlarg_borrowed!('a, &'a str,  |s| SqlArg::Str(Cow::Borrowed(s)));
 impl_from_for_sqlarg_borrowed!('a, &'a [u8], |b| SqlArg::Bytes(Cow::Borrowed(b)));
+impl_from_for_sqlarg_borrowed!('a, Cow<'a, str>, SqlArg::Str);
+impl_from_for_sqlarg_borrowed!('a, &'a Arc<str>, |s: &'a Arc<str>| {
+    SqlArg::Str(Cow::Borrowed(s))
+});
+// The value is copied into the request anyway; bind `&arc` to skip the
+// intermediate String
+impl_from_for_sqlarg!(Arc<str>, |s: Arc<str>| SqlArg::Str(Cow::Owned(
+    s.to_string()
+)));
+
+impl<const N: usize> From<[u8; N]> for SqlArg<'static> {
+    fn from(b: [u8; N]) -> Self {
+        SqlArg::Bytes(Cow::Owned(b.to_vec()))
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for SqlArg<'a> {
+    fn from(b: &'a [u8; N]) -> Self {
+        SqlArg::Bytes(Cow::Borrowed(b))
+    }
+}
 
 impl<'a, T> From<Option<T>> for SqlArg<'a>
 where
@@ -110,59 +153,287 @@ fn arg_to_sql_value(a: SqlArg<'_>) -> SqlValue {
         SqlArg::Str(s) => sql_value::Value::S(s.into_owned()),
         SqlArg::Bytes(b) => sql_value::Value::Bs(b.into_owned()),
         SqlArg::Ts(ts) => sql_value::Value::Ts(ts),
+        SqlArg::Json(json) => return json_to_sql_value(json),
     };
     SqlValue { value: Some(v) }
 }
 
-/// Convenient params collection API
+fn json_to_sql_value(json: JsonValue) -> SqlValue {
+    let arg = match json {
+        JsonValue::Null => SqlArg::Null,
+        JsonValue::Bool(b) => SqlArg::Bool(b),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => SqlArg::I64(i),
+            None => SqlArg::F64(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        JsonValue::String(s) => SqlArg::Str(Cow::Owned(s)),
+        json @ (JsonValue::Array(_) | JsonValue::Object(_)) => {
+            SqlArg::Str(Cow::Owned(json.to_string()))
+        }
+    };
+    arg_to_sql_value(arg)
+}
+
+/// Convenient params collection API.
+///
+/// Every `exec`/`query` method also takes `&Params`, so one set of params
+/// can be reused across statements (it's cloned per call).
 #[derive(Default, Debug, Clone)]
 pub struct Params {
     inner: Vec<NamedParam>,
 }
+
+/// Lets `&Params` be passed wherever `impl Into<Params>` is expected
+impl ToParams for Params {
+    fn to_params(&self) -> Params {
+        self.clone()
+    }
+}
 impl Params {
     pub fn new() -> Self {
         Self { inner: Vec::new() }
     }
     /// name — without '@'. In sql use `@name`.
+    /// A leading '@'/'$' is stripped (and logged), see [`Params::try_bind`].
     pub fn bind<'a>(
         mut self,
         name: impl Into<String>,
         val: impl Into<SqlArg<'a>>,
     ) -> Self {
+        let mut name: String = name.into();
+        if let Some(stripped) = name.strip_prefix(['@', '$']) {
+            tracing::warn!(%name, "sql param name must not be prefixed");
+            name = stripped.to_string();
+        }
         let arg: SqlArg<'a> = val.into();
         self.inner.push(NamedParam {
-            name: name.into(),
+            name,
             value: Some(arg_to_sql_value(arg)),
         });
         self
     }
-    pub fn bind_dt(
-        mut self,
+    /// Like [`Params::bind`], but a name starting with '@'/'$' is an
+    /// `Error::InvalidInput` instead of being fixed up.
+    pub fn try_bind<'a>(
+        self,
+        name: impl Into<String>,
+        val: impl Into<SqlArg<'a>>,
+    ) -> Result<Self> {
+        let name: String = name.into();
+        if name.starts_with(['@', '$']) {
+            return Err(Error::InvalidInput(format!(
+                "sql param name '{name}' must not be prefixed with '@' or '$'"
+            )));
+        }
+        Ok(self.bind(name, val))
+    }
+    /// Binds SQL `NULL`, same as binding `None::<T>`. A parameter that is
+    /// referenced but never bound is not NULL: immudb rejects the
+    /// statement with a missing parameter error.
+    pub fn bind_null(self, name: impl Into<String>) -> Self {
+        self.bind(name, SqlArg::Null)
+    }
+    pub fn bind_dt(self, name: impl Into<String>, dt: OffsetDateTime) -> Self {
+        self.bind(name, dt)
+    }
+    /// Binds a wall-clock datetime taken in `offset`. immudb stores only
+    /// UTC microseconds, so the offset itself is lost: read it back with
+    /// [`decode_dt_in`] and the same offset.
+    pub fn bind_dt_with_offset(
+        self,
+        name: impl Into<String>,
+        dt: PrimitiveDateTime,
+        offset: UtcOffset,
+    ) -> Self {
+        self.bind_dt(name, dt.assume_offset(offset))
+    }
+    /// Binds `dt` as whole Unix seconds in an `INTEGER`. immudb's own
+    /// `TIMESTAMP` is always UTC microseconds, which is what
+    /// [`Params::bind_dt`] sends; use this (or [`Params::bind_ts_millis`])
+    /// for integer columns holding epoch times. Decode with [`decode_ts_secs`].
+    pub fn bind_ts_secs(
+        self,
         name: impl Into<String>,
         dt: OffsetDateTime,
     ) -> Self {
-        self.inner.push(NamedParam {
-            name: name.into(),
-            value: Some(arg_to_sql_value(SqlArg::from(dt))),
-        });
-        self
+        self.bind(name, dt.unix_timestamp())
+    }
+    /// Binds `dt` as Unix milliseconds in an `INTEGER`, see
+    /// [`Params::bind_ts_secs`]. Decode with [`decode_ts_millis`].
+    pub fn bind_ts_millis(
+        self,
+        name: impl Into<String>,
+        dt: OffsetDateTime,
+    ) -> Self {
+        let millis = dt.unix_timestamp_nanos() / 1_000_000;
+        self.bind(name, millis as i64)
+    }
+    /// Binds UTF-8 text held as bytes as a VARCHAR; invalid UTF-8 is an
+    /// `Error::InvalidInput`
+    pub fn bind_utf8(
+        self,
+        name: impl Into<String>,
+        bytes: &[u8],
+    ) -> Result<Self> {
+        let s = std::str::from_utf8(bytes).map_err(|e| {
+            Error::InvalidInput(format!("sql param is not valid UTF-8: {e}"))
+        })?;
+        Ok(self.bind(name, s))
+    }
+    /// Like [`Params::bind_utf8`], but invalid sequences become U+FFFD
+    pub fn bind_str_lossy(self, name: impl Into<String>, bytes: &[u8]) -> Self {
+        self.bind(name, SqlArg::Str(String::from_utf8_lossy(bytes)))
+    }
+    /// Binds a JSON value, see [`SqlArg::Json`] for the mapping
+    pub fn bind_json(self, name: impl Into<String>, json: &JsonValue) -> Self {
+        self.bind(name, SqlArg::Json(json.clone()))
     }
     pub fn into_inner(self) -> Vec<NamedParam> {
         self.inner
     }
 }
 
-/// SELECT-queries results
+/// Result of one executed statement (INSERT, UPSERT, ...)
+#[derive(Debug, Clone, Default)]
+pub struct ExecOutcome {
+    /// Committed transaction; `None` inside an explicit transaction, where
+    /// the commit happens later
+    pub tx_id: Option<u64>,
+    pub updated_rows: u32,
+    /// Last auto-increment primary key inserted, by table name
+    pub last_inserted_pks: HashMap<String, SqlValue>,
+    /// First auto-increment primary key inserted, by table name
+    pub first_inserted_pks: HashMap<String, SqlValue>,
+}
+
+impl ExecOutcome {
+    /// Fold every transaction committed by one statement into one outcome
+    fn from_txs(txs: Vec<CommittedSqlTx>) -> Self {
+        let mut out = ExecOutcome::default();
+        for tx in txs {
+            out.tx_id = tx.header.map(|h| h.id).or(out.tx_id);
+            out.updated_rows += tx.updated_rows;
+            for (table, pk) in tx.first_inserted_p_ks {
+                out.first_inserted_pks.entry(table).or_insert(pk);
+            }
+            out.last_inserted_pks.extend(tx.last_inserted_p_ks);
+        }
+        out
+    }
+}
+
+impl From<SqlExecResult> for ExecOutcome {
+    fn from(r: SqlExecResult) -> Self {
+        Self::from_txs(r.txs)
+    }
+}
+
+/// SELECT-queries results. Not exhaustive: build one with [`Column::new`].
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Column {
     pub name: String,
     pub r#type: String,
+    /// immudb reports nullability only through `describe_table`; for
+    /// query results it is `Some(true)` when a NULL was seen in the
+    /// column, `None` (unknown) otherwise
+    pub nullable: Option<bool>,
+}
+
+impl Column {
+    /// Column of unknown nullability
+    pub fn new(name: impl Into<String>, r#type: impl Into<String>) -> Self {
+        Column {
+            name: name.into(),
+            r#type: r#type.into(),
+            nullable: None,
+        }
+    }
+}
+
+impl From<crate::schema::Column> for Column {
+    fn from(c: crate::schema::Column) -> Self {
+        Column::new(c.name, c.r#type)
+    }
 }
 #[derive(Debug, Clone)]
 pub struct Row {
     pub columns: Vec<String>,
     pub values: Vec<SqlValue>,
 }
+impl Row {
+    /// Value of column `idx`, converted with its `TryFrom<SqlValue>` impl
+    pub fn get<T: TryFrom<SqlValue, Error = Error>>(
+        &self,
+        idx: usize,
+    ) -> Result<T> {
+        T::try_from(self.value(idx)?)
+    }
+
+    /// Value of column `name`, as `users.id`, `(users.id)` or just `id`
+    /// when that is unambiguous; `cols` are the result's columns.
+    pub fn get_by_name<T: TryFrom<SqlValue, Error = Error>>(
+        &self,
+        name: &str,
+        cols: &[Column],
+    ) -> Result<T> {
+        self.get(self.index_of(name, cols)?)
+    }
+
+    /// Like [`Row::get_by_name`], but `None` for a NULL value
+    pub fn get_opt_by_name<T: TryFrom<SqlValue, Error = Error>>(
+        &self,
+        name: &str,
+        cols: &[Column],
+    ) -> Result<Option<T>> {
+        let v = self.value(self.index_of(name, cols)?)?;
+        match v.value {
+            Some(sql_value::Value::Null(_)) | None => Ok(None),
+            _ => T::try_from(v).map(Some),
+        }
+    }
+
+    fn value(&self, idx: usize) -> Result<SqlValue> {
+        self.values.get(idx).cloned().ok_or_else(|| {
+            Error::Decode(format!(
+                "column {idx} out of bounds ({} columns)",
+                self.values.len()
+            ))
+        })
+    }
+
+    fn index_of(&self, name: &str, cols: &[Column]) -> Result<usize> {
+        let names: Vec<&str> = if !self.columns.is_empty() {
+            self.columns.iter().map(String::as_str).collect()
+        } else {
+            cols.iter().map(|c| c.name.as_str()).collect()
+        };
+        let wanted = QueryResult::qualified_col(name);
+        if let Some(idx) = names
+            .iter()
+            .position(|n| QueryResult::qualified_col(n) == wanted)
+        {
+            return Ok(idx);
+        }
+
+        let short = QueryResult::normalize_col(name);
+        let mut matches = names
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| QueryResult::normalize_col(n) == short);
+        match (matches.next(), matches.next()) {
+            (Some((idx, _)), None) => Ok(idx),
+            (Some(_), Some(_)) => {
+                Err(Error::Decode(format!("column '{name}' is ambiguous")))
+            }
+            (None, _) => Err(Error::Decode(format!("no column '{name}'"))),
+        }
+    }
+}
+
+/// Rows of a query, merged from every streamed `SqlQueryResult` chunk.
+/// Chunks carry only column descriptions and rows: the server reports no
+/// transaction id or row counts for reads, so there is nothing else to keep.
 #[derive(Debug, Clone)]
 pub struct QueryResult {
     pub columns: Vec<Column>,
@@ -176,10 +447,38 @@ impl QueryResult {
     pub fn len(&self) -> usize {
         self.rows.len()
     }
+    /// Columns marked nullable where a row holds NULL
+    fn with_seen_nulls(mut columns: Vec<Column>, rows: Vec<Row>) -> Self {
+        for (i, col) in columns.iter_mut().enumerate() {
+            let null = rows.iter().any(|row| {
+                matches!(
+                    row.values.get(i).and_then(|v| v.value.as_ref()),
+                    Some(sql_value::Value::Null(_))
+                )
+            });
+            if null {
+                col.nullable = Some(true);
+            }
+        }
+        QueryResult { columns, rows }
+    }
 
-    /// Convenient row conversion to JSON-objec (bytes -> base64)
-    fn short(name: &str) -> &str {
-        name.rsplit('.').next().unwrap_or(name)
+    /// Take the rows without cloning them
+    pub fn into_rows(self) -> Vec<Row> {
+        self.rows
+    }
+
+    /// Column names exactly as the server sent them, e.g. `(users.id)`
+    pub fn column_names_raw(&self) -> Vec<&str> {
+        self.columns.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    /// Column names without table qualifier or quoting, e.g. `id`
+    pub fn column_names_normalized(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|c| Self::normalize_col(&c.name))
+            .collect()
     }
 
     fn normalize_col(mut s: &str) -> String {
@@ -188,7 +487,7 @@ impl QueryResult {
         loop {
             let b = s.as_bytes();
             if s.len() >= 2 && b[0] == b'(' && b[s.len() - 1] == b')' {
-                s = &s[1..s.len() - 1].trim();
+                s = s[1..s.len() - 1].trim();
             } else {
                 break;
             }
@@ -205,12 +504,69 @@ impl QueryResult {
             .to_string()
     }
 
+    /// `(users.id)` -> `users.id`
+    fn qualified_col(s: &str) -> String {
+        s.trim()
+            .trim_matches(|c: char| c == '(' || c == ')')
+            .trim()
+            .to_string()
+    }
+
+    /// Convenient row conversion to JSON-objec (bytes -> base64)
     pub fn row_as_json(&self, idx: usize) -> Result<serde_json::Value> {
+        let obj = self
+            .row_entries(idx)?
+            .into_iter()
+            .map(|(key, _, json)| (key, json))
+            .collect();
+        Ok(serde_json::Value::Object(obj))
+    }
+
+    /// Like [`QueryResult::row_as_json`], but keys are the column names
+    /// exactly as the server reports them (e.g. `(users.id)`)
+    pub fn row_as_json_raw(&self, idx: usize) -> Result<serde_json::Value> {
+        let obj = self
+            .row_entries_with(idx, false)?
+            .into_iter()
+            .map(|(key, _, json)| (key, json))
+            .collect();
+        Ok(serde_json::Value::Object(obj))
+    }
+
+    /// Like [`QueryResult::row_as_json`], but every column carries its
+    /// declared type: `{"col": {"type": "INTEGER", "value": 3}}`
+    pub fn row_as_json_typed(&self, idx: usize) -> Result<serde_json::Value> {
+        let obj = self
+            .row_entries(idx)?
+            .into_iter()
+            .map(|(key, ty, json)| {
+                (key, serde_json::json!({ "type": ty, "value": json }))
+            })
+            .collect();
+        Ok(serde_json::Value::Object(obj))
+    }
+
+    /// (normalized name, column type, JSON value) for every value of a row.
+    /// A column whose short name is already taken (`a.id` and `b.id` of a
+    /// join) keeps its qualified name instead.
+    fn row_entries(
+        &self,
+        idx: usize,
+    ) -> Result<Vec<(String, String, serde_json::Value)>> {
+        self.row_entries_with(idx, true)
+    }
+
+    fn row_entries_with(
+        &self,
+        idx: usize,
+        normalize: bool,
+    ) -> Result<Vec<(String, String, serde_json::Value)>> {
         let row = self
             .rows
             .get(idx)
             .ok_or_else(|| Error::Decode("row out of bounds".into()))?;
-        let mut obj = serde_json::Map::new();
+        let mut entries = Vec::with_capacity(row.values.len());
+        let mut taken = HashSet::new();
 
         // At first try per-row labels, otherwise - global
         let names: Vec<String> = if !row.columns.is_empty() {
@@ -232,7 +588,21 @@ impl QueryResult {
                     .cloned()
                     .unwrap_or_else(|| format!("col{}", i + 1))
             };
-            let key = Self::normalize_col(&raw);
+            let key = if !normalize {
+                raw
+            } else {
+                let short = Self::normalize_col(&raw);
+                if taken.contains(&short) {
+                    Self::qualified_col(&raw)
+                } else {
+                    short
+                }
+            };
+            if !taken.insert(key.clone()) {
+                return Err(Error::Decode(format!(
+                    "duplicate column '{key}', alias it with AS"
+                )));
+            }
 
             let v = row.values.get(i).cloned().unwrap_or(
                 crate::protocol::schema::SqlValue {
@@ -240,21 +610,52 @@ impl QueryResult {
                 },
             );
 
-            obj.insert(key, sql_value_to_json(v));
+            let ty = self
+                .columns
+                .get(i)
+                .map(|c| c.r#type.clone())
+                .unwrap_or_default();
+            // UUID columns come back as 16 raw bytes: emit the canonical
+            // text form so `rows_as` can deserialize them into `Uuid`
+            let is_uuid = ty.eq_ignore_ascii_case("UUID");
+            let json = match v.value {
+                Some(sql_value::Value::Bs(ref bs)) if is_uuid => {
+                    match Uuid::from_slice(bs) {
+                        Ok(u) => JsonValue::String(u.to_string()),
+                        Err(_) => sql_value_to_json(v),
+                    }
+                }
+                _ => sql_value_to_json(v),
+            };
+
+            entries.push((key, ty, json));
         }
 
-        Ok(serde_json::Value::Object(obj))
+        Ok(entries)
+    }
+
+    /// Every row through [`FromRow`], without going through serde
+    pub fn rows_into<T: FromRow>(&self) -> Result<Vec<T>> {
+        self.rows
+            .iter()
+            .map(|row| T::from_row(row, &self.columns))
+            .collect()
     }
 
     /// Deserialize all rows into T (using JSON). Fields are matched by column names.
     pub fn rows_as<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
-        let mut out = Vec::with_capacity(self.rows.len());
-        for i in 0..self.rows.len() {
+        self.deserialize_iter().collect()
+    }
+
+    /// Like [`QueryResult::rows_as`], one row at a time: rows after the
+    /// point where iteration stops are never converted
+    pub fn deserialize_iter<T: DeserializeOwned>(
+        &self,
+    ) -> impl Iterator<Item = Result<T>> + '_ {
+        (0..self.rows.len()).map(|i| {
             let v = self.row_as_json(i)?;
-            let t = serde_json::from_value::<T>(v)?;
-            out.push(t);
-        }
-        Ok(out)
+            Ok(serde_json::from_value::<T>(v)?)
+        })
     }
 
     /// One scalar (first column, first row)
@@ -271,6 +672,7 @@ impl QueryResult {
         T::try_from(v)
     }
 
+    /// Like `scalar`, but `None` for an empty result or a NULL value
     pub fn scalar_opt<T>(&self) -> Result<Option<T>>
     where
         T: TryFrom<SqlValue, Error = Error>,
@@ -292,6 +694,7 @@ impl QueryResult {
         }
     }
 
+    /// First column of every row (e.g. a list of ids)
     pub fn first_col_as<T>(&self) -> Result<Vec<T>>
     where
         T: TryFrom<SqlValue, Error = Error>,
@@ -299,7 +702,7 @@ impl QueryResult {
         let mut out = Vec::with_capacity(self.rows.len());
         for row in &self.rows {
             let v =
-                row.values.get(0).cloned().ok_or_else(|| {
+                row.values.first().cloned().ok_or_else(|| {
                     Error::Decode("row has no columns".into())
                 })?;
             out.push(T::try_from(v)?);
@@ -307,6 +710,7 @@ impl QueryResult {
         Ok(out)
     }
 
+    /// Deserialize the only row into T; errors unless there's exactly one
     pub fn one_as<T: DeserializeOwned>(&self) -> Result<T> {
         if self.rows.len() != 1 {
             return Err(Error::Decode(format!(
@@ -319,6 +723,364 @@ impl QueryResult {
     }
 }
 
+/// Make user input match literally in a `LIKE` pattern. immudb's `LIKE`
+/// takes a (Go) regular expression rather than `%`/`_` wildcards, so this
+/// escapes the regex metacharacters, backslash included. Bind the result
+/// instead of splicing it into the SQL:
+///
+/// ```ignore
+/// let pattern = format!("^{}", escape_like(search));
+/// let params = Params::new().bind("pattern", pattern.as_str());
+/// sql.query("SELECT * FROM users WHERE name LIKE @pattern", params)
+///     .await?;
+/// ```
+pub fn escape_like(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        if r"\.+*?()|[]{}^$".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// immudb's wording for values (bound or literal) of the wrong SQL type
+fn is_type_mismatch(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["invalid types", "invalid value provided", "mismatched type"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
+
+/// Bound parameter named in a server message, as `@name` or after
+/// `param`/`parameter`. Only names of `bound` params are accepted, so an
+/// `@` elsewhere in the message (e.g. in a quoted value) isn't taken for
+/// one.
+fn mismatched_param<'a>(message: &str, bound: &'a [String]) -> Option<&'a str> {
+    let ident = |s: &str| {
+        let end = s
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(s.len());
+        s[..end].to_string()
+    };
+    let after_at = message
+        .match_indices('@')
+        .map(|(i, _)| ident(&message[i + 1..]));
+    let after_keyword =
+        ["parameter ", "param "].into_iter().flat_map(|keyword| {
+            message.match_indices(keyword).map(move |(i, _)| {
+                ident(
+                    message[i + keyword.len()..]
+                        .trim_start_matches(['\'', '"', '`']),
+                )
+            })
+        });
+    after_at.chain(after_keyword).find_map(|candidate| {
+        bound
+            .iter()
+            .find(|name| {
+                !candidate.is_empty() && name.eq_ignore_ascii_case(&candidate)
+            })
+            .map(String::as_str)
+    })
+}
+
+fn param_names(params: &[NamedParam]) -> Vec<String> {
+    params.iter().map(|p| p.name.clone()).collect()
+}
+
+/// Turn immudb's parser errors ("syntax error: unexpected ... at position
+/// 7") into `Error::Sql` and type mismatches into `Error::InvalidInput`,
+/// naming the offending param among the `bound` ones; anything else stays
+/// `Error::Protocol`
+fn sql_error(e: impl Into<Error>, bound: &[String]) -> Error {
+    let e = e.into();
+    let Error::Protocol(status) = &e else {
+        return e;
+    };
+    let message = status.message();
+    if is_type_mismatch(message) {
+        return Error::InvalidInput(match mismatched_param(message, bound) {
+            Some(param) => format!("param @{param}: {message}"),
+            None => message.to_string(),
+        });
+    }
+    if !message.contains("syntax error") {
+        return e;
+    }
+    let position = message.rsplit_once("at position ").and_then(|(_, rest)| {
+        let digits = rest.split(|c: char| !c.is_ascii_digit()).next()?;
+        digits.parse().ok()
+    });
+    Error::Sql {
+        message: message.to_string(),
+        position,
+    }
+}
+
+/// immudb refusing a statement inside a transaction: DDL mixed with DML,
+/// or a statement that can't be transactional at all
+fn is_ddl_in_tx(status: &tonic::Status) -> bool {
+    const MESSAGES: [&str; 2] = [
+        "either DDL or DML statements are accepted within a single \
+         transaction",
+        "non-transactional statement",
+    ];
+    MESSAGES.iter().any(|m| status.message().contains(m))
+}
+
+const MIGRATION_RECORD: &str =
+    "INSERT INTO _migrations (version, applied_at) VALUES (@version, NOW())";
+
+/// Plain identifier (`[A-Za-z_][A-Za-z0-9_]*`), so it can be spliced into
+/// DDL without quoting
+fn check_ident(kind: &str, ident: &str) -> Result<()> {
+    let mut chars = ident.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "invalid {kind} name '{ident}'"
+        )))
+    }
+}
+
+/// Column type like `INTEGER` or `VARCHAR[256]`
+fn check_sql_type(sql_type: &str) -> Result<()> {
+    let (base, len) = match sql_type.split_once('[') {
+        Some((base, rest)) => (base, rest.strip_suffix(']')),
+        None => (sql_type, Some("0")),
+    };
+    let valid = !base.is_empty()
+        && base.chars().all(|c| c.is_ascii_alphabetic())
+        && len.is_some_and(|l| {
+            !l.is_empty() && l.chars().all(|c| c.is_ascii_digit())
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "invalid sql type '{sql_type}'"
+        )))
+    }
+}
+
+fn add_column_ddl(
+    table: &str,
+    name: &str,
+    sql_type: &str,
+    nullable: bool,
+) -> Result<String> {
+    check_ident("table", table)?;
+    check_ident("column", name)?;
+    check_sql_type(sql_type)?;
+    let not_null = if nullable { "" } else { " NOT NULL" };
+    Ok(format!(
+        "ALTER TABLE {table} ADD COLUMN {name} {sql_type}{not_null}"
+    ))
+}
+
+fn upsert_sql(table: &str, params: &Params) -> Result<String> {
+    check_ident("table", table)?;
+    if params.inner.is_empty() {
+        return Err(Error::InvalidInput(
+            "upsert needs at least one param".into(),
+        ));
+    }
+    let mut cols = Vec::with_capacity(params.inner.len());
+    for p in &params.inner {
+        check_ident("column", &p.name)?;
+        cols.push(p.name.as_str());
+    }
+    let values: Vec<String> = cols.iter().map(|c| format!("@{c}")).collect();
+    Ok(format!(
+        "UPSERT INTO {table} ({}) VALUES ({})",
+        cols.join(", "),
+        values.join(", ")
+    ))
+}
+
+fn rename_column_ddl(table: &str, old: &str, new: &str) -> Result<String> {
+    check_ident("table", table)?;
+    check_ident("column", old)?;
+    check_ident("column", new)?;
+    Ok(format!("ALTER TABLE {table} RENAME COLUMN {old} TO {new}"))
+}
+
+/// What a character of a SQL text belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Span {
+    Code,
+    /// String literal or quoted identifier, quotes included
+    Quoted,
+    /// `-- ...` up to the end of the line, or `/* ... */`
+    Comment,
+}
+
+/// Classify every character of `sql`, for the parsing helpers below
+fn scan_sql(sql: &str) -> Vec<(char, Span)> {
+    let mut out = Vec::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut quote: Option<char> = None;
+    let mut block_comment = false;
+    let mut line_comment = false;
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        let span = if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            Span::Quoted
+        } else if line_comment {
+            if c == '\n' {
+                line_comment = false;
+                Span::Code
+            } else {
+                Span::Comment
+            }
+        } else if block_comment {
+            if c == '*' && next == Some('/') {
+                block_comment = false;
+                out.push((c, Span::Comment));
+                chars.next();
+                out.push(('/', Span::Comment));
+                continue;
+            }
+            Span::Comment
+        } else {
+            match (c, next) {
+                ('\'' | '"' | '`', _) => {
+                    quote = Some(c);
+                    Span::Quoted
+                }
+                ('-', Some('-')) => {
+                    line_comment = true;
+                    Span::Comment
+                }
+                ('/', Some('*')) => {
+                    block_comment = true;
+                    out.push((c, Span::Comment));
+                    chars.next();
+                    out.push(('*', Span::Comment));
+                    continue;
+                }
+                _ => Span::Code,
+            }
+        };
+        out.push((c, span));
+    }
+    out
+}
+
+/// Split a script on top-level `;`, ignoring ones inside string literals,
+/// quoted identifiers and comments. Comments are replaced by a space and
+/// empty statements are dropped.
+fn split_statements(sql: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut prev = Span::Code;
+    for (c, span) in scan_sql(sql) {
+        match span {
+            Span::Code if c == ';' => out.push(std::mem::take(&mut current)),
+            Span::Comment if prev != Span::Comment => current.push(' '),
+            Span::Comment => {}
+            _ => current.push(c),
+        }
+        prev = span;
+    }
+    out.push(current);
+    out.into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// `sql` with `LIMIT`/`OFFSET` for 1-based `page`. A query that already
+/// has either keyword outside quotes and comments (subqueries included)
+/// is rejected.
+fn paged_sql(sql: &str, page: u32, page_size: u32) -> Result<String> {
+    if page == 0 || page_size == 0 {
+        return Err(Error::InvalidInput(
+            "page and page_size start at 1".into(),
+        ));
+    }
+    let [sql] = split_statements(sql).try_into().map_err(|_| {
+        Error::InvalidInput("paged query must be a single statement".into())
+    })?;
+
+    let mut word = String::new();
+    for (c, span) in scan_sql(&sql).into_iter().chain([(' ', Span::Code)]) {
+        if span == Span::Code && (c.is_alphanumeric() || c == '_') {
+            word.push(c);
+            continue;
+        }
+        if word.eq_ignore_ascii_case("LIMIT")
+            || word.eq_ignore_ascii_case("OFFSET")
+        {
+            return Err(Error::InvalidInput(format!(
+                "paged query already has {}",
+                word.to_uppercase()
+            )));
+        }
+        word.clear();
+    }
+
+    let offset = u64::from(page - 1) * u64::from(page_size);
+    Ok(format!("{sql} LIMIT {page_size} OFFSET {offset}"))
+}
+
+/// `@name` placeholders referenced by `sql` (lowercased), skipping string
+/// literals, quoted identifiers and comments
+fn sql_param_names(sql: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut chars = scan_sql(sql).into_iter().peekable();
+    while let Some((c, span)) = chars.next() {
+        if span != Span::Code || c != '@' {
+            continue;
+        }
+        let mut name = String::new();
+        while let Some(&(n, _)) = chars.peek().filter(|(n, span)| {
+            *span == Span::Code && (n.is_alphanumeric() || *n == '_')
+        }) {
+            name.push(n.to_ascii_lowercase());
+            chars.next();
+        }
+        if !name.is_empty() {
+            names.insert(name);
+        }
+    }
+    names
+}
+
+impl IntoIterator for QueryResult {
+    type Item = Row;
+    type IntoIter = std::vec::IntoIter<Row>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+impl From<SqlQueryResult> for QueryResult {
+    fn from(r: SqlQueryResult) -> Self {
+        QueryResult::with_seen_nulls(
+            r.columns.into_iter().map(Column::from).collect(),
+            r.rows
+                .into_iter()
+                .map(|r| Row {
+                    columns: r.columns,
+                    values: r.values,
+                })
+                .collect(),
+        )
+    }
+}
+
 fn sql_value_to_json(v: SqlValue) -> JsonValue {
     use sql_value::Value::*;
     match v.value {
@@ -333,6 +1095,110 @@ fn sql_value_to_json(v: SqlValue) -> JsonValue {
     }
 }
 
+/// Blobs longer than this (in hex chars) are truncated in `Display`
+const DISPLAY_BLOB_MAX: usize = 32;
+
+fn sql_value_to_display(v: &SqlValue) -> String {
+    use sql_value::Value::*;
+    match &v.value {
+        Some(Null(_)) | None => "NULL".into(),
+        Some(N(n)) => n.to_string(),
+        Some(F(f)) => f.to_string(),
+        Some(B(b)) => b.to_string(),
+        Some(S(s)) => s.clone(),
+        Some(Bs(bs)) => {
+            let hex = hex::encode(bs);
+            if hex.len() > DISPLAY_BLOB_MAX {
+                format!("0x{}…", &hex[..DISPLAY_BLOB_MAX])
+            } else {
+                format!("0x{hex}")
+            }
+        }
+        Some(Ts(us)) => {
+            OffsetDateTime::from_unix_timestamp_nanos((*us as i128) * 1_000)
+                .ok()
+                .and_then(|dt| dt.format(&Rfc3339).ok())
+                .unwrap_or_else(|| us.to_string())
+        }
+    }
+}
+
+/// Aligned text table, e.g. for CLI output
+impl std::fmt::Display for QueryResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .rows
+            .iter()
+            .map(|r| r.values.len())
+            .max()
+            .unwrap_or(0)
+            .max(self.columns.len());
+
+        let header: Vec<String> = (0..width)
+            .map(|i| match self.columns.get(i) {
+                Some(c) => Self::normalize_col(&c.name),
+                None => format!("col{}", i + 1),
+            })
+            .collect();
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|r| {
+                (0..width)
+                    .map(|i| {
+                        r.values
+                            .get(i)
+                            .map(sql_value_to_display)
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut widths: Vec<usize> =
+            header.iter().map(|h| h.chars().count()).collect();
+        for row in &cells {
+            for (w, cell) in widths.iter_mut().zip(row) {
+                *w = (*w).max(cell.chars().count());
+            }
+        }
+
+        let write_line = |f: &mut std::fmt::Formatter<'_>,
+                          line: &[String]|
+         -> std::fmt::Result {
+            let line: Vec<String> = line
+                .iter()
+                .zip(&widths)
+                .map(|(cell, w)| format!("{cell:<w$}"))
+                .collect();
+            writeln!(f, "{}", line.join(" | ").trim_end())
+        };
+
+        write_line(f, &header)?;
+        let sep: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        writeln!(f, "{}", sep.join("-+-"))?;
+        for row in &cells {
+            write_line(f, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialized as an array of row objects (see [`QueryResult::row_as_json`])
+impl Serialize for QueryResult {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.rows.len()))?;
+        for i in 0..self.rows.len() {
+            let row = self.row_as_json(i).map_err(serde::ser::Error::custom)?;
+            seq.serialize_element(&row)?;
+        }
+        seq.end()
+    }
+}
+
 #[macro_export]
 macro_rules! impl_tryfrom_sqlvalue {
     ($ty:ty, $expected:expr, $( $pat:pat => $expr:expr ),+ $(,)?) => {
@@ -357,6 +1223,21 @@ impl_tryfrom_sqlvalue!(i64, "i64",
     sql_value::Value::N(n) => n,
 );
 
+/// Narrower integers decode from `N` with a range check
+macro_rules! impl_tryfrom_sqlvalue_int {
+    ($($ty:ty),+ $(,)?) => {$(
+        impl_tryfrom_sqlvalue!($ty, stringify!($ty),
+            sql_value::Value::N(n) => <$ty>::try_from(n).map_err(|_| {
+                Error::Decode(format!(
+                    "{n} is out of range for {}", stringify!($ty)
+                ))
+            })?,
+        );
+    )+};
+}
+
+impl_tryfrom_sqlvalue_int!(i32, u32, u64, usize);
+
 impl_tryfrom_sqlvalue!(String, "string or bytes(base64)",
     sql_value::Value::S(s)  => s,
     sql_value::Value::Bs(b) => BASE64_STANDARD.encode(b),
@@ -383,16 +1264,73 @@ impl_tryfrom_sqlvalue!(OffsetDateTime, "timestamp (Ts)",
     },
 );
 
+/// Decode a timestamp into `offset` instead of UTC. The stored value has
+/// no offset of its own, see [`Params::bind_dt_with_offset`].
+pub fn decode_dt_in(v: SqlValue, offset: UtcOffset) -> Result<OffsetDateTime> {
+    OffsetDateTime::try_from(v).map(|dt| dt.to_offset(offset))
+}
+
+/// 16 raw bytes, or uuid text stored as bytes
+fn uuid_from_bytes(bs: &[u8]) -> Result<Uuid> {
+    if let Ok(u) = Uuid::from_slice(bs) {
+        return Ok(u);
+    }
+    std::str::from_utf8(bs)
+        .ok()
+        .and_then(|s| Uuid::parse_str(s.trim()).ok())
+        .ok_or_else(|| {
+            Error::Decode(format!(
+                "expected uuid as 16 bytes or text, got {} bytes",
+                bs.len()
+            ))
+        })
+}
+
+/// Decode an `INTEGER` of Unix seconds, see [`Params::bind_ts_secs`]
+pub fn decode_ts_secs(v: SqlValue) -> Result<OffsetDateTime> {
+    let secs = i64::try_from(v)?;
+    OffsetDateTime::from_unix_timestamp(secs)
+        .map_err(|e| Error::Decode(e.to_string()))
+}
+
+/// Decode an `INTEGER` of Unix milliseconds, see [`Params::bind_ts_millis`]
+pub fn decode_ts_millis(v: SqlValue) -> Result<OffsetDateTime> {
+    let millis = i64::try_from(v)?;
+    OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000)
+        .map_err(|e| Error::Decode(e.to_string()))
+}
+
 impl_tryfrom_sqlvalue!(uuid::Uuid, "uuid (16 bytes or string)",
-    sql_value::Value::Bs(bs) => uuid::Uuid::from_slice(&bs)
-        .map_err(|e| crate::error::Error::Decode(e.to_string()))?,
+    sql_value::Value::Bs(bs) => uuid_from_bytes(&bs)?,
     sql_value::Value::S(s) => uuid::Uuid::parse_str(&s)
         .map_err(|e| crate::error::Error::Decode(e.to_string()))?,
 );
 
-/// Client: exec/query/tx API
-#[derive(Clone)]
-pub struct SqlClient {
+/// Transaction mode for [`SqlClient::begin`]; converts into the protocol
+/// [`TxMode`], which is accepted as well
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transaction {
+    /// Only reads; sees a snapshot taken at `begin`
+    ReadOnly,
+    /// Reads and writes; conflicting concurrent writes fail the commit
+    ReadWrite,
+    /// Only writes, no snapshot is taken
+    WriteOnly,
+}
+
+impl From<Transaction> for TxMode {
+    fn from(t: Transaction) -> Self {
+        match t {
+            Transaction::ReadOnly => TxMode::ReadOnly,
+            Transaction::ReadWrite => TxMode::ReadWrite,
+            Transaction::WriteOnly => TxMode::WriteOnly,
+        }
+    }
+}
+
+/// Client: exec/query/tx API
+#[derive(Clone)]
+pub struct SqlClient {
     inner: ImmuServiceClient<
         tonic::service::interceptor::InterceptedService<
             tonic::transport::Channel,
@@ -400,6 +1338,9 @@ pub struct SqlClient {
         >,
     >,
     tx_id: Option<MetadataValue<Ascii>>,
+    retry: RetryPolicy,
+    strict_params: bool,
+    last_tx: Option<TxHeader>,
 }
 
 impl SqlClient {
@@ -407,7 +1348,65 @@ impl SqlClient {
         Self {
             inner: db.raw_main(),
             tx_id: None,
+            retry: db.retry_policy(),
+            strict_params: false,
+            last_tx: None,
+        }
+    }
+
+    /// Header (id, timestamp, hashes) of the last transaction committed
+    /// through this client, by an exec outside a transaction or a commit.
+    /// Attested by the server only: nothing is verified.
+    pub fn last_tx_header(&self) -> Option<&TxHeader> {
+        self.last_tx.as_ref()
+    }
+
+    /// Header of transaction `tx_id`, as reported by the server (not
+    /// verified), e.g. for an audit trail
+    pub async fn tx_info(&mut self, tx_id: u64) -> Result<TxHeader> {
+        let req = TxRequest {
+            tx: tx_id,
+            // Every entry kind excluded: only the header is needed
+            entries_spec: Some(EntriesSpec::default()),
+            ..Default::default()
+        };
+        with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            async move { cli.tx_by_id(req).await }
+        })
+        .await?
+        .into_inner()
+        .header
+        .ok_or_else(|| Error::Decode(format!("tx {tx_id} has no header")))
+    }
+
+    /// Check bound params against the `@name` placeholders of every
+    /// statement before sending it. immudb only rejects an unbound
+    /// placeholder once the statement runs ("missing parameter") and
+    /// ignores unused params, so a typo in a bound name goes unnoticed;
+    /// with this on either mismatch is an `Error::InvalidInput` up front.
+    pub fn strict_params(mut self, strict: bool) -> Self {
+        self.strict_params = strict;
+        self
+    }
+
+    fn check_params(&self, sql: &str, params: &[NamedParam]) -> Result<()> {
+        if !self.strict_params {
+            return Ok(());
+        }
+        let referenced = sql_param_names(sql);
+        let bound: BTreeSet<String> =
+            params.iter().map(|p| p.name.to_ascii_lowercase()).collect();
+
+        let unbound: Vec<_> = referenced.difference(&bound).collect();
+        let unused: Vec<_> = bound.difference(&referenced).collect();
+        if unbound.is_empty() && unused.is_empty() {
+            return Ok(());
         }
+        tracing::warn!(?unbound, ?unused, "sql params mismatch");
+        Err(Error::InvalidInput(format!(
+            "sql params mismatch: unbound {unbound:?}, unused {unused:?}"
+        )))
     }
 
     fn req_with_tx<T>(&self, payload: T) -> Request<T> {
@@ -427,18 +1426,141 @@ impl SqlClient {
     where
         P: Into<Params>,
     {
+        self.exec_with(sql.into(), params.into(), false).await
+    }
+
+    /// Same as [`SqlClient::exec`], but sends `no_wait: true`: the server
+    /// acknowledges the statement without waiting for the commit to be
+    /// indexed. Lower latency for bulk loads, but a read issued right after
+    /// may not observe the written rows yet.
+    pub async fn exec_no_wait<P>(
+        &mut self,
+        sql: impl Into<String>,
+        params: P,
+    ) -> Result<SqlExecResult>
+    where
+        P: Into<Params>,
+    {
+        self.exec_with(sql.into(), params.into(), true).await
+    }
+
+    /// Execute a multi-statement script one statement at a time, returning
+    /// one outcome per statement; `--` and `/* */` comments are dropped.
+    /// `params` are sent with every statement.
+    /// Stops at the first failing statement; earlier ones stay applied
+    /// unless the script runs inside a transaction.
+    pub async fn exec_multi<P>(
+        &mut self,
+        sql: impl AsRef<str>,
+        params: P,
+    ) -> Result<Vec<ExecOutcome>>
+    where
+        P: Into<Params>,
+    {
+        let params = params.into();
+        let mut outcomes = Vec::new();
+        for stmt in split_statements(sql.as_ref()) {
+            let mut stmt_params = params.clone();
+            if self.strict_params {
+                // Shared params are expected to be unused by some statements
+                let referenced = sql_param_names(&stmt);
+                stmt_params.inner.retain(|p| {
+                    referenced.contains(&p.name.to_ascii_lowercase())
+                });
+            }
+            let res = self.exec_with(stmt, stmt_params, false).await?;
+            outcomes.push(ExecOutcome::from(res));
+        }
+        Ok(outcomes)
+    }
+
+    /// Run `statements` in one read-write transaction: they all commit
+    /// together or, on the first failure, are all rolled back. immudb's
+    /// exec RPC takes a single parameter set, so this is still one round
+    /// trip per statement (plus begin/commit). Inside an already open
+    /// transaction the statements just join it.
+    ///
+    /// Per-statement counts aren't reported inside a transaction: every
+    /// outcome carries the committed tx id, and the last one also the row
+    /// count and inserted pks of the whole transaction.
+    pub async fn exec_batch(
+        &mut self,
+        statements: Vec<(String, Params)>,
+    ) -> Result<Vec<ExecOutcome>> {
+        let n = statements.len();
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        if self.tx_id.is_some() {
+            for (sql, params) in statements {
+                self.exec_with(sql, params, false).await?;
+            }
+            return Ok(vec![ExecOutcome::default(); n]);
+        }
+
+        self.begin(Transaction::ReadWrite).await?;
+        for (sql, params) in statements {
+            if let Err(e) = self.exec_with(sql, params, false).await {
+                let _ = self.rollback().await;
+                return Err(e);
+            }
+        }
+        let total = self
+            .commit_inner()
+            .await?
+            .map(|committed| ExecOutcome::from_txs(vec![committed]))
+            .unwrap_or_default();
+
+        let mut outcomes = vec![
+            ExecOutcome {
+                tx_id: total.tx_id,
+                ..Default::default()
+            };
+            n - 1
+        ];
+        outcomes.push(total);
+        Ok(outcomes)
+    }
+
+    async fn exec_with(
+        &mut self,
+        sql: String,
+        params: Params,
+        no_wait: bool,
+    ) -> Result<SqlExecResult> {
         let req = SqlExecRequest {
-            sql: sql.into(),
-            params: params.into().into_inner(),
-            no_wait: false,
+            sql,
+            params: params.into_inner(),
+            no_wait,
         };
+        self.check_params(&req.sql, &req.params)?;
+        let bound = param_names(&req.params);
+        let sql_error = |e| sql_error(e, &bound);
         let resp = if self.tx_id.is_some() {
             let req = self.req_with_tx(req);
-            let _ = self.inner.tx_sql_exec(req).await?;
+            let _ = self.inner.tx_sql_exec(req).await.map_err(sql_error)?;
             SqlExecResult::default()
+        } else if self.retry.retry_writes {
+            with_retry(&self.retry, || {
+                let mut cli = self.inner.clone();
+                let req = req.clone();
+                async move { cli.sql_exec(req).await }
+            })
+            .await
+            .map_err(sql_error)?
+            .into_inner()
         } else {
-            self.inner.sql_exec(req).await?.into_inner()
+            self.inner
+                .sql_exec(req)
+                .await
+                .map_err(sql_error)?
+                .into_inner()
         };
+        if let Some(header) =
+            resp.txs.iter().rev().find_map(|tx| tx.header.clone())
+        {
+            self.last_tx = Some(header);
+        }
         Ok(resp)
     }
 
@@ -451,49 +1573,141 @@ impl SqlClient {
     where
         P: Into<Params>,
     {
-        let req = SqlQueryRequest {
-            sql: sql.into(),
-            params: params.into().into_inner(),
-            accept_stream: true,
-            ..Default::default()
-        };
-        let req = self.req_with_tx(req);
-        let mut stream = if self.tx_id.is_some() {
-            self.inner.tx_sql_query(req).await?.into_inner()
-        } else {
-            self.inner.sql_query(req).await?.into_inner()
-        };
+        match self.query_partial(sql, params).await? {
+            (qr, None) => Ok(qr),
+            (_, Some(e)) => Err(e),
+        }
+    }
+
+    /// Like [`SqlClient::query`], but when the result stream fails midway
+    /// the rows received so far are returned along with the error.
+    /// Failing to start the query is still an `Err`.
+    pub async fn query_partial<P>(
+        &mut self,
+        sql: impl Into<String>,
+        params: P,
+    ) -> Result<(QueryResult, Option<Error>)>
+    where
+        P: Into<Params>,
+    {
+        let mut stream = self.open_query(sql.into(), params.into()).await?;
 
         let mut columns_meta: Vec<Column> = Vec::new();
         let mut rows: Vec<Row> = Vec::new();
 
-        while let Some(chunk) = stream.message().await? {
+        let error = loop {
+            let chunk = match stream.message().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break None,
+                Err(e) => break Some(Error::from(e)),
+            };
             if columns_meta.is_empty() && !chunk.columns.is_empty() {
-                columns_meta = chunk
-                    .columns
-                    .into_iter()
-                    .map(|c| Column {
-                        name: c.name,
-                        r#type: c.r#type,
-                    })
-                    .collect();
+                columns_meta =
+                    chunk.columns.into_iter().map(Column::from).collect();
             }
             rows.extend(chunk.rows.into_iter().map(|r| Row {
                 columns: r.columns,
                 values: r.values,
             }));
+        };
+
+        let qr = QueryResult::with_seen_nulls(columns_meta, rows);
+        Ok((qr, error))
+    }
+
+    /// True if the query yields at least one row. Stops reading the result
+    /// stream at the first row, so it's cheap on large results.
+    pub async fn exists<P>(
+        &mut self,
+        sql: impl Into<String>,
+        params: P,
+    ) -> Result<bool>
+    where
+        P: Into<Params>,
+    {
+        let mut stream = self.open_query(sql.into(), params.into()).await?;
+        while let Some(chunk) = stream.message().await? {
+            if !chunk.rows.is_empty() {
+                return Ok(true);
+            }
         }
+        Ok(false)
+    }
 
-        Ok(QueryResult {
-            columns: columns_meta,
-            rows,
-        })
+    /// One page of a `SELECT`: `LIMIT page_size OFFSET (page - 1) *
+    /// page_size` is appended, pages start at 1. Give the query an
+    /// `ORDER BY`, or pages may overlap.
+    pub async fn query_paged<P>(
+        &mut self,
+        sql: impl AsRef<str>,
+        params: P,
+        page: u32,
+        page_size: u32,
+    ) -> Result<QueryResult>
+    where
+        P: Into<Params>,
+    {
+        let sql = paged_sql(sql.as_ref(), page, page_size)?;
+        self.query(sql, params).await
     }
 
-    pub async fn query_scalar<T>(
+    /// Single non-negative integer, e.g. of `SELECT COUNT(*) ...`
+    pub async fn count_scalar<P>(
         &mut self,
         sql: impl Into<String>,
+        params: P,
+    ) -> Result<u64>
+    where
+        P: Into<Params>,
+    {
+        self.query(sql, params).await?.scalar()
+    }
+
+    /// The server's clock (`SELECT NOW()`), e.g. to stamp rows
+    /// consistently with server-side `NOW()` defaults. Inside a transaction
+    /// this is the transaction's timestamp.
+    pub async fn server_now(&mut self) -> Result<OffsetDateTime> {
+        self.query("SELECT NOW()", Params::new()).await?.scalar()
+    }
+
+    async fn open_query(
+        &mut self,
+        sql: String,
         params: Params,
+    ) -> Result<Streaming<SqlQueryResult>> {
+        let req = SqlQueryRequest {
+            sql,
+            params: params.into_inner(),
+            accept_stream: true,
+            ..Default::default()
+        };
+        self.check_params(&req.sql, &req.params)?;
+        let bound = param_names(&req.params);
+        let sql_error = |e| sql_error(e, &bound);
+        let stream = if self.tx_id.is_some() {
+            let req = self.req_with_tx(req);
+            self.inner
+                .tx_sql_query(req)
+                .await
+                .map_err(sql_error)?
+                .into_inner()
+        } else {
+            with_retry(&self.retry, || {
+                let mut cli = self.inner.clone();
+                let req = req.clone();
+                async move { cli.sql_query(req).await }
+            })
+            .await
+            .map_err(sql_error)?
+            .into_inner()
+        };
+        Ok(stream)
+    }
+
+    pub async fn query_scalar<T>(
+        &mut self,
+        sql: impl Into<String>,
+        params: impl Into<Params>,
     ) -> Result<T>
     where
         T: TryFrom<SqlValue, Error = Error>,
@@ -505,7 +1719,7 @@ impl SqlClient {
     pub async fn query_as<T: DeserializeOwned>(
         &mut self,
         sql: impl Into<String>,
-        params: Params,
+        params: impl Into<Params>,
     ) -> Result<Vec<T>> {
         self.query(sql, params).await?.rows_as::<T>()
     }
@@ -523,6 +1737,145 @@ impl SqlClient {
         qr.first_col_as()
     }
 
+    /// Rows through [`FromRow`] (e.g. `#[derive(FromRow)]`), using the
+    /// `TryFrom<SqlValue>` impls rather than serde
+    pub async fn query_rows<T, P>(
+        &mut self,
+        sql: impl Into<String>,
+        params: P,
+    ) -> Result<Vec<T>>
+    where
+        P: Into<Params>,
+        T: FromRow,
+    {
+        self.query(sql, params).await?.rows_into()
+    }
+
+    /// Map every row by hand (e.g. with the `TryFrom<SqlValue>` impls),
+    /// without going through serde
+    pub async fn query_map<T, P, F>(
+        &mut self,
+        sql: impl Into<String>,
+        params: P,
+        mut f: F,
+    ) -> Result<Vec<T>>
+    where
+        P: Into<Params>,
+        F: FnMut(&Row, &[Column]) -> Result<T>,
+    {
+        let qr = self.query(sql, params).await?;
+        qr.rows.iter().map(|row| f(row, &qr.columns)).collect()
+    }
+
+    /// Write every row of `table` to `w` as a JSON line (same encoding
+    /// as [`QueryResult::row_as_json`]); returns the number of rows. Rows
+    /// are written chunk by chunk as the server streams them.
+    pub async fn export_table<W: std::io::Write>(
+        &mut self,
+        table: &str,
+        w: &mut W,
+    ) -> Result<u64> {
+        check_ident("table", table)?;
+        let mut stream = self
+            .open_query(format!("SELECT * FROM {table}"), Params::new())
+            .await?;
+
+        let mut chunk_result = QueryResult {
+            columns: Vec::new(),
+            rows: Vec::new(),
+        };
+        let mut written = 0;
+        while let Some(chunk) = stream.message().await? {
+            if chunk_result.columns.is_empty() {
+                chunk_result.columns =
+                    chunk.columns.into_iter().map(Column::from).collect();
+            }
+            chunk_result.rows = chunk
+                .rows
+                .into_iter()
+                .map(|r| Row {
+                    columns: r.columns,
+                    values: r.values,
+                })
+                .collect();
+            for idx in 0..chunk_result.rows.len() {
+                let line =
+                    serde_json::to_string(&chunk_result.row_as_json(idx)?)?;
+                writeln!(w, "{line}").map_err(|e| {
+                    Error::Unexpected(format!("failed to write row: {e}"))
+                })?;
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Insert one row into `table` per JSON object line of `r` (blank
+    /// lines are skipped), all in one transaction; returns the number of
+    /// rows. Columns come from the object keys, which must be the same on
+    /// every line; values are bound like [`Params::bind_json`].
+    pub async fn import_jsonl<R: std::io::BufRead>(
+        &mut self,
+        table: &str,
+        r: R,
+    ) -> Result<u64> {
+        check_ident("table", table)?;
+        let mut columns: Option<Vec<String>> = None;
+        let mut statements = Vec::new();
+
+        for (n, line) in r.lines().enumerate() {
+            let line = line.map_err(|e| {
+                Error::Unexpected(format!("failed to read line {}: {e}", n + 1))
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let JsonValue::Object(obj) = serde_json::from_str(&line)? else {
+                return Err(Error::InvalidInput(format!(
+                    "line {}: expected a JSON object",
+                    n + 1
+                )));
+            };
+
+            let mut keys: Vec<String> = obj.keys().cloned().collect();
+            keys.sort();
+            let columns = match &columns {
+                Some(columns) if *columns != keys => {
+                    return Err(Error::InvalidInput(format!(
+                        "line {}: columns {keys:?} differ from {columns:?} \
+                         of the first row",
+                        n + 1
+                    )));
+                }
+                Some(columns) => columns,
+                None => {
+                    for key in &keys {
+                        check_ident("column", key)?;
+                    }
+                    columns.insert(keys)
+                }
+            };
+
+            let sql = format!(
+                "INSERT INTO {table} ({}) VALUES ({})",
+                columns.join(", "),
+                columns
+                    .iter()
+                    .map(|c| format!("@{c}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            let params = obj
+                .into_iter()
+                .fold(Params::new(), |p, (k, v)| p.bind(k, SqlArg::Json(v)));
+            statements.push((sql, params));
+        }
+
+        let count = statements.len() as u64;
+        self.exec_batch(statements).await?;
+        Ok(count)
+    }
+
     pub async fn query_one_as<T, P>(
         &mut self,
         sql: impl Into<String>,
@@ -565,9 +1918,113 @@ impl SqlClient {
         self.query(sql, params).await?.scalar_opt()
     }
 
+    /// Names of the tables in the current database
+    pub async fn list_tables(&mut self) -> Result<Vec<String>> {
+        let qr: QueryResult = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            async move { cli.list_tables(()).await }
+        })
+        .await?
+        .into_inner()
+        .into();
+        qr.first_col_as()
+    }
+
+    /// Column names and types of `table`, as reported by immudb's catalog
+    /// (`TYPE` looks like `INTEGER`, `VARCHAR[256]`, ...)
+    pub async fn describe_table(
+        &mut self,
+        table: impl Into<String>,
+    ) -> Result<Vec<Column>> {
+        let req = Table {
+            table_name: table.into(),
+        };
+        let qr: QueryResult = with_retry(&self.retry, || {
+            let mut cli = self.inner.clone();
+            let req = req.clone();
+            async move { cli.describe_table(req).await }
+        })
+        .await?
+        .into_inner()
+        .into();
+
+        let position = |name: &str| {
+            qr.columns.iter().position(|c| {
+                QueryResult::normalize_col(&c.name).eq_ignore_ascii_case(name)
+            })
+        };
+        let name_idx = position("COLUMN").unwrap_or(0);
+        let type_idx = position("TYPE").unwrap_or(1);
+        let nullable_idx = position("NULLABLE");
+
+        qr.rows
+            .iter()
+            .map(|row| {
+                let get = |i: usize| {
+                    row.values.get(i).cloned().ok_or_else(|| {
+                        Error::Decode(format!("describe row has no column {i}"))
+                    })
+                };
+                Ok(Column {
+                    name: String::try_from(get(name_idx)?)?,
+                    r#type: String::try_from(get(type_idx)?)?,
+                    nullable: nullable_idx
+                        .map(|i| bool::try_from(get(i)?))
+                        .transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    /// `UPSERT INTO table (<param names>) VALUES (<params>)`, returning the
+    /// auto-increment primary key immudb assigned. A row whose key is given
+    /// in `params` gets none assigned, which is an `Error::Decode`.
+    pub async fn upsert_returning_pk(
+        &mut self,
+        table: &str,
+        params: impl Into<Params>,
+    ) -> Result<SqlValue> {
+        let params = params.into();
+        let sql = upsert_sql(table, &params)?;
+        let outcome = ExecOutcome::from(self.exec(sql, params).await?);
+        outcome
+            .last_inserted_pks
+            .into_iter()
+            .find(|(t, _)| t.eq_ignore_ascii_case(table))
+            .map(|(_, pk)| pk)
+            .ok_or_else(|| {
+                Error::Decode(format!("no primary key assigned in '{table}'"))
+            })
+    }
+
+    /// `ALTER TABLE table ADD COLUMN name sql_type [NOT NULL]`;
+    /// `sql_type` as in `CREATE TABLE`, e.g. `INTEGER` or `VARCHAR[256]`
+    pub async fn add_column(
+        &mut self,
+        table: &str,
+        name: &str,
+        sql_type: &str,
+        nullable: bool,
+    ) -> Result<ExecOutcome> {
+        let ddl = add_column_ddl(table, name, sql_type, nullable)?;
+        Ok(self.exec(ddl, Params::new()).await?.into())
+    }
+
+    /// `ALTER TABLE table RENAME COLUMN old TO new`
+    pub async fn rename_column(
+        &mut self,
+        table: &str,
+        old: &str,
+        new: &str,
+    ) -> Result<ExecOutcome> {
+        let ddl = rename_column_ddl(table, old, new)?;
+        Ok(self.exec(ddl, Params::new()).await?.into())
+    }
+
     /// Simple transaction (server keeps ongoing_tx in session)
     #[tracing::instrument(skip_all)]
-    pub async fn begin(&mut self, mode: TxMode) -> Result<()> {
+    pub async fn begin(&mut self, mode: impl Into<TxMode>) -> Result<()> {
+        let mode: TxMode = mode.into();
         let NewTxResponse { transaction_id } = self
             .inner
             .new_tx(NewTxRequest {
@@ -586,13 +2043,33 @@ impl SqlClient {
 
     #[tracing::instrument(skip_all)]
     pub async fn commit(&mut self) -> Result<()> {
+        self.commit_inner().await?;
+        Ok(())
+    }
+
+    /// Like [`SqlClient::commit`], but returns the id of the committed
+    /// transaction. Fails if there's no ongoing transaction.
+    #[tracing::instrument(skip_all)]
+    pub async fn commit_tx(&mut self) -> Result<u64> {
+        let committed = self.commit_inner().await?.ok_or_else(|| {
+            Error::InvalidInput("no ongoing transaction".into())
+        })?;
+        committed.header.map(|h| h.id).ok_or_else(|| {
+            Error::Decode("commit response has no header".into())
+        })
+    }
+
+    async fn commit_inner(&mut self) -> Result<Option<CommittedSqlTx>> {
         if self.tx_id.is_none() {
-            return Ok(());
+            return Ok(None);
         }
         let req = self.req_with_tx(());
-        let _ = self.inner.commit(req).await?;
+        let committed = self.inner.commit(req).await?.into_inner();
         self.tx_id = None;
-        Ok(())
+        if committed.header.is_some() {
+            self.last_tx = committed.header.clone();
+        }
+        Ok(Some(committed))
     }
 
     #[tracing::instrument(skip_all)]
@@ -607,7 +2084,11 @@ impl SqlClient {
     }
 
     #[tracing::instrument(skip_all)]
-    pub async fn with_tx<T, F>(&mut self, mode: TxMode, f: F) -> Result<T>
+    pub async fn with_tx<T, F>(
+        &mut self,
+        mode: impl Into<TxMode>,
+        f: F,
+    ) -> Result<T>
     where
         F: for<'a> FnOnce(&'a mut SqlClient) -> BoxFut<'a, T>,
     {
@@ -623,4 +2104,1914 @@ impl SqlClient {
             }
         }
     }
+
+    /// Apply pending `(version, sql)` migrations in version order, recording
+    /// each in a `_migrations` table (created if absent). Each migration runs
+    /// in its own read-write transaction together with its record. If immudb
+    /// rejects it inside a transaction because DDL can't run there (mixed
+    /// with DML, or a non-transactional statement), it is retried statement
+    /// by statement outside one; a failure at that point can leave the
+    /// migration partially applied and unrecorded. Any other error is
+    /// returned as is.
+    pub async fn migrate(&mut self, migrations: &[(i64, &str)]) -> Result<()> {
+        let mut pending = migrations.to_vec();
+        pending.sort_by_key(|(version, _)| *version);
+        if let Some(w) = pending.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(Error::InvalidInput(format!(
+                "duplicate migration version {}",
+                w[0].0
+            )));
+        }
+
+        self.exec(
+            "CREATE TABLE IF NOT EXISTS _migrations (\
+             version INTEGER, applied_at TIMESTAMP, PRIMARY KEY version)",
+            Params::new(),
+        )
+        .await?;
+        let applied: HashSet<i64> = self
+            .query_col("SELECT version FROM _migrations", Params::new())
+            .await?
+            .into_iter()
+            .collect();
+        pending.retain(|(version, _)| !applied.contains(version));
+
+        for (version, sql) in pending {
+            let record = Params::new().bind("version", version);
+            let in_tx = self
+                .with_tx(Transaction::ReadWrite, |cli| {
+                    // The future must not borrow `migrations`
+                    let (sql, record) = (sql.to_string(), record.clone());
+                    Box::pin(async move {
+                        cli.exec_multi(sql, Params::new()).await?;
+                        cli.exec(MIGRATION_RECORD, record).await?;
+                        Ok(())
+                    })
+                })
+                .await;
+            match in_tx {
+                Ok(()) => {}
+                Err(Error::Protocol(status)) if is_ddl_in_tx(&status) => {
+                    tracing::warn!(
+                        version,
+                        %status,
+                        "migration failed in a transaction, retrying without"
+                    );
+                    self.exec_multi(sql, Params::new()).await?;
+                    self.exec(MIGRATION_RECORD, record).await?;
+                }
+                Err(e) => return Err(e),
+            }
+            tracing::info!(version, "migration applied");
+        }
+        Ok(())
+    }
+}
+
+/// Object-safe facade over [`SqlClient`], for storing a client behind
+/// `Box<dyn DynSqlClient>` (generic methods of `SqlClient` rule that out)
+#[async_trait::async_trait]
+pub trait DynSqlClient: Send {
+    async fn exec(
+        &mut self,
+        sql: String,
+        params: Params,
+    ) -> Result<SqlExecResult>;
+    async fn query(
+        &mut self,
+        sql: String,
+        params: Params,
+    ) -> Result<QueryResult>;
+    async fn begin(&mut self, mode: TxMode) -> Result<()>;
+    async fn commit(&mut self) -> Result<()>;
+    async fn rollback(&mut self) -> Result<()>;
+}
+
+#[async_trait::async_trait]
+impl DynSqlClient for SqlClient {
+    async fn exec(
+        &mut self,
+        sql: String,
+        params: Params,
+    ) -> Result<SqlExecResult> {
+        SqlClient::exec(self, sql, params).await
+    }
+    async fn query(
+        &mut self,
+        sql: String,
+        params: Params,
+    ) -> Result<QueryResult> {
+        SqlClient::query(self, sql, params).await
+    }
+    async fn begin(&mut self, mode: TxMode) -> Result<()> {
+        SqlClient::begin(self, mode).await
+    }
+    async fn commit(&mut self) -> Result<()> {
+        SqlClient::commit(self).await
+    }
+    async fn rollback(&mut self) -> Result<()> {
+        SqlClient::rollback(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::mock::Mock;
+    use crate::schema::{Column as ProtoColumn, Row as ProtoRow};
+
+    fn int(v: i64) -> SqlValue {
+        SqlValue {
+            value: Some(sql_value::Value::N(v)),
+        }
+    }
+
+    /// What the fake `_migrations` server was asked to do
+    #[derive(Default)]
+    struct Migrations {
+        applied: Vec<i64>,
+        /// Statements run outside a transaction, in order
+        direct: Vec<String>,
+        /// Statements of the open transaction, with the recorded version
+        pending: Vec<(String, Option<i64>)>,
+        committed: Vec<String>,
+        rollbacks: usize,
+    }
+
+    fn recorded_version(req: &SqlExecRequest) -> Option<i64> {
+        if !req.sql.starts_with("INSERT INTO _migrations") {
+            return None;
+        }
+        req.params
+            .iter()
+            .find_map(|p| match p.value.as_ref()?.value {
+                Some(sql_value::Value::N(v)) if p.name == "version" => Some(v),
+                _ => None,
+            })
+    }
+
+    /// Statements containing `DDL_ONLY` are refused in a transaction the
+    /// way immudb refuses DDL mixed with DML; `BROKEN` always fails
+    fn migrations_server(state: &Arc<Mutex<Migrations>>) -> Mock {
+        let (exec, tx_exec, query, commit, rollback) = (
+            state.clone(),
+            state.clone(),
+            state.clone(),
+            state.clone(),
+            state.clone(),
+        );
+        Mock::new()
+            .unary("SQLExec", move |req: SqlExecRequest| {
+                let mut state = exec.lock().unwrap();
+                state.direct.push(req.sql.clone());
+                if req.sql.contains("BROKEN") {
+                    return Err(tonic::Status::unknown("table does not exist"));
+                }
+                if let Some(version) = recorded_version(&req) {
+                    state.applied.push(version);
+                }
+                Ok(SqlExecResult::default())
+            })
+            .stream("SQLQuery", move |_: SqlQueryRequest| {
+                let state = query.lock().unwrap();
+                Ok(vec![SqlQueryResult {
+                    columns: vec![ProtoColumn {
+                        name: "(_migrations.version)".into(),
+                        r#type: "INTEGER".into(),
+                    }],
+                    rows: state
+                        .applied
+                        .iter()
+                        .map(|v| ProtoRow {
+                            columns: vec!["(_migrations.version)".into()],
+                            values: vec![int(*v)],
+                        })
+                        .collect(),
+                }])
+            })
+            .unary("NewTx", |_: NewTxRequest| {
+                Ok(NewTxResponse {
+                    transaction_id: "tx-1".into(),
+                })
+            })
+            .unary("TxSQLExec", move |req: SqlExecRequest| {
+                if req.sql.contains("DDL_ONLY") {
+                    return Err(tonic::Status::invalid_argument(
+                        "either DDL or DML statements are accepted within \
+                         a single transaction",
+                    ));
+                }
+                if req.sql.contains("BROKEN") {
+                    return Err(tonic::Status::unknown("table does not exist"));
+                }
+                let version = recorded_version(&req);
+                tx_exec.lock().unwrap().pending.push((req.sql, version));
+                Ok(())
+            })
+            .unary("Commit", move |_: ()| {
+                let mut state = commit.lock().unwrap();
+                for (sql, version) in std::mem::take(&mut state.pending) {
+                    state.applied.extend(version);
+                    state.committed.push(sql);
+                }
+                Ok(CommittedSqlTx::default())
+            })
+            .unary("Rollback", move |_: ()| {
+                let mut state = rollback.lock().unwrap();
+                state.pending.clear();
+                state.rollbacks += 1;
+                Ok(())
+            })
+    }
+
+    #[test]
+    fn split_statements_on_top_level_semicolons() {
+        let cases: [(&str, &[&str]); 9] = [
+            ("", &[]),
+            ("  ;\n; ", &[]),
+            ("SELECT 1", &["SELECT 1"]),
+            ("SELECT 1;  SELECT 2;\n\t", &["SELECT 1", "SELECT 2"]),
+            (
+                "INSERT INTO t VALUES ('a;b'); SELECT \"x;y\" FROM `t;`",
+                &["INSERT INTO t VALUES ('a;b')", "SELECT \"x;y\" FROM `t;`"],
+            ),
+            ("SELECT 'it''s; fine'", &["SELECT 'it''s; fine'"]),
+            (
+                "-- setup; not a statement\nCREATE TABLE t; -- done;",
+                &["CREATE TABLE t"],
+            ),
+            ("SELECT 1 /* ; */; /* only a comment */ ;", &["SELECT 1"]),
+            // A comment separates tokens; in a literal it's text
+            (
+                "SELECT a/**/FROM t WHERE s = '--x'",
+                &["SELECT a FROM t WHERE s = '--x'"],
+            ),
+        ];
+        for (sql, expected) in cases {
+            assert_eq!(split_statements(sql), expected, "{sql:?}");
+        }
+    }
+
+    #[test]
+    fn paged_sql_appends_limit_and_offset() {
+        assert_eq!(
+            paged_sql("SELECT * FROM t ORDER BY id;\n", 1, 20).unwrap(),
+            "SELECT * FROM t ORDER BY id LIMIT 20 OFFSET 0"
+        );
+        assert_eq!(
+            paged_sql("SELECT * FROM t ORDER BY id", 3, 20).unwrap(),
+            "SELECT * FROM t ORDER BY id LIMIT 20 OFFSET 40"
+        );
+        // Keywords in literals, quoted names and comments don't count
+        assert_eq!(
+            paged_sql(
+                "SELECT \"limit\" FROM t -- no LIMIT\nWHERE s = 'OFFSET'",
+                2,
+                5
+            )
+            .unwrap(),
+            "SELECT \"limit\" FROM t  \nWHERE s = 'OFFSET' LIMIT 5 OFFSET 5"
+        );
+    }
+
+    #[test]
+    fn paged_sql_rejects_bad_input() {
+        let cases = [
+            ("SELECT 1", 0, 10, "page and page_size start at 1"),
+            ("SELECT 1", 1, 0, "page and page_size start at 1"),
+            ("", 1, 10, "paged query must be a single statement"),
+            (
+                "SELECT 1; SELECT 2",
+                1,
+                10,
+                "paged query must be a single statement",
+            ),
+            (
+                "SELECT * FROM t limit 5",
+                1,
+                10,
+                "paged query already has LIMIT",
+            ),
+            (
+                "SELECT * FROM (SELECT * FROM t OFFSET 1)",
+                1,
+                10,
+                "paged query already has OFFSET",
+            ),
+        ];
+        for (sql, page, size, expected) in cases {
+            let err = paged_sql(sql, page, size).unwrap_err();
+            assert!(
+                matches!(&err, Error::InvalidInput(m) if m == expected),
+                "{sql:?}: {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sql_param_names_skip_literals_and_comments() {
+        let names = |sql| sql_param_names(sql).into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            names("SELECT * FROM t WHERE a = @A AND b > @b_2 OR c = @a"),
+            ["a", "b_2"]
+        );
+        assert_eq!(
+            names(
+                "UPDATE t SET mail = 'x@host', \"a@b\" = @v -- @commented\n\
+                 WHERE id = @id /* @block */"
+            ),
+            ["id", "v"]
+        );
+        // A lone `@` is no placeholder
+        assert!(names("SELECT '@' || @").is_empty());
+        assert!(names("").is_empty());
+    }
+
+    #[test]
+    fn narrow_integers_check_their_range() {
+        assert_eq!(i32::try_from(int(i32::MAX.into())).unwrap(), i32::MAX);
+        assert_eq!(i32::try_from(int(i32::MIN.into())).unwrap(), i32::MIN);
+        assert!(i32::try_from(int(i64::from(i32::MAX) + 1)).is_err());
+        assert!(i32::try_from(int(i64::from(i32::MIN) - 1)).is_err());
+
+        assert_eq!(u32::try_from(int(0)).unwrap(), 0);
+        assert_eq!(u32::try_from(int(u32::MAX.into())).unwrap(), u32::MAX);
+        assert!(u32::try_from(int(i64::from(u32::MAX) + 1)).is_err());
+        let err = u32::try_from(int(-1)).unwrap_err();
+        assert!(
+            matches!(&err, Error::Decode(m) if m == "-1 is out of range for u32"),
+            "{err:?}"
+        );
+
+        assert_eq!(u64::try_from(int(i64::MAX)).unwrap(), i64::MAX as u64);
+        assert!(u64::try_from(int(-1)).is_err());
+        assert_eq!(usize::try_from(int(0)).unwrap(), 0);
+        assert!(usize::try_from(int(-1)).is_err());
+
+        let text = SqlValue {
+            value: Some(sql_value::Value::S("1".into())),
+        };
+        assert!(matches!(i32::try_from(text), Err(Error::Decode(_))));
+    }
+
+    #[test]
+    fn escape_like_escapes_regex_metacharacters() {
+        assert_eq!(escape_like("plain text"), "plain text");
+        // immudb's LIKE is a regex: SQL wildcards are ordinary characters
+        assert_eq!(escape_like("50%_off"), "50%_off");
+        assert_eq!(escape_like(r"a\b"), r"a\\b");
+        for c in r".+*?()|[]{}^$".chars() {
+            assert_eq!(escape_like(&c.to_string()), format!("\\{c}"));
+        }
+        assert_eq!(escape_like("(a.b)*"), r"\(a\.b\)\*");
+        assert_eq!(escape_like("é.ü"), r"é\.ü");
+    }
+
+    fn map_status(message: &str, bound: &[&str]) -> Error {
+        let bound: Vec<String> = bound.iter().map(|b| b.to_string()).collect();
+        sql_error(tonic::Status::unknown(message), &bound)
+    }
+
+    #[test]
+    fn syntax_errors_carry_the_position() {
+        let message = "syntax error: unexpected IDENTIFIER, expecting TABLE at position 14";
+        let err = map_status(message, &[]);
+        assert!(
+            matches!(&err, Error::Sql { message: m, position: Some(14) } if m == message),
+            "{err:?}"
+        );
+        let err = map_status("syntax error: unexpected $end", &[]);
+        assert!(matches!(err, Error::Sql { position: None, .. }), "{err:?}");
+    }
+
+    #[test]
+    fn type_mismatches_name_a_bound_param() {
+        let cases = [
+            (
+                "invalid types: VARCHAR can not be interpreted as type INTEGER",
+                &["age"][..],
+                "invalid types: VARCHAR can not be interpreted as type INTEGER",
+            ),
+            (
+                "invalid value provided: @Age is not an INTEGER",
+                &["age"],
+                "param @age: invalid value provided: @Age is not an INTEGER",
+            ),
+            (
+                "mismatched type for parameter 'age'",
+                &["age", "name"],
+                "param @age: mismatched type for parameter 'age'",
+            ),
+            // Not anchored on the first `@`: only bound names count
+            (
+                "invalid value provided: 'me@host' for @email",
+                &["email"],
+                "param @email: invalid value provided: 'me@host' for @email",
+            ),
+            (
+                "invalid value provided: 'me@host'",
+                &["email"],
+                "invalid value provided: 'me@host'",
+            ),
+        ];
+        for (message, bound, expected) in cases {
+            let err = map_status(message, bound);
+            assert!(
+                matches!(&err, Error::InvalidInput(m) if m == expected),
+                "{message}: {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn other_errors_pass_through() {
+        let err = map_status("table does not exist (users)", &["users"]);
+        assert!(matches!(err, Error::Protocol(_)), "{err:?}");
+        let err = sql_error(tonic::Status::deadline_exceeded("late"), &[]);
+        assert!(matches!(err, Error::RequestTimeout(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn transaction_modes_map_to_the_protocol() {
+        let server = Mock::new()
+            .unary("NewTx", |_: NewTxRequest| {
+                Ok(NewTxResponse {
+                    transaction_id: "tx".into(),
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let modes = [
+            (Transaction::ReadOnly, 0),
+            (Transaction::WriteOnly, 1),
+            (Transaction::ReadWrite, 2),
+        ];
+        for (mode, proto) in modes {
+            assert_eq!(TxMode::from(mode) as i32, proto);
+            db.sql().begin(mode).await.unwrap();
+        }
+        let sent: Vec<i32> = server
+            .calls_to("NewTx")
+            .iter()
+            .map(|c| c.decode::<NewTxRequest>().mode)
+            .collect();
+        assert_eq!(sent, [0, 1, 2]);
+    }
+
+    fn exec_server() -> Mock {
+        Mock::new()
+            .unary("SQLExec", |_: SqlExecRequest| Ok(SqlExecResult::default()))
+    }
+
+    #[test]
+    fn bind_null_sends_a_null_value() {
+        let params = Params::new().bind_null("deleted_at").into_inner();
+        assert_eq!(
+            params,
+            [NamedParam {
+                name: "deleted_at".into(),
+                value: Some(SqlValue {
+                    value: Some(sql_value::Value::Null(0)),
+                }),
+            }]
+        );
+        assert_eq!(Params::new().bind("deleted_at", None::<i64>).inner, params);
+    }
+
+    #[test]
+    fn every_binder_strips_the_name_prefix() {
+        let at = time::macros::datetime!(2024-05-01 12:00 UTC);
+        let params = Params::new()
+            .bind("@id", 1)
+            .bind_null("$gone")
+            .bind_dt("@at", at)
+            .bind_dt_with_offset(
+                "$local",
+                time::macros::datetime!(2024-05-01 14:00),
+                time::macros::offset!(+2),
+            )
+            .bind_ts_secs("@secs", at)
+            .bind_json("@doc", &serde_json::json!({"a": 1}));
+        let names: Vec<_> =
+            params.into_inner().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, ["id", "gone", "at", "local", "secs", "doc"]);
+    }
+
+    #[test]
+    fn try_bind_rejects_prefixed_names() {
+        let params = Params::new().try_bind("id", 1).unwrap();
+        assert_eq!(params.inner[0].name, "id");
+        for name in ["@id", "$id"] {
+            let err = Params::new().try_bind(name, 1).unwrap_err();
+            assert!(
+                matches!(&err, Error::InvalidInput(m) if m.contains(name)),
+                "{err:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn exec_no_wait_sets_no_wait() {
+        let server = exec_server().start().await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        sql.exec("DELETE FROM t", Params::new()).await.unwrap();
+        sql.exec_no_wait(
+            "DELETE FROM t WHERE id = @id",
+            Params::new().bind("id", 1),
+        )
+        .await
+        .unwrap();
+        let sent: Vec<SqlExecRequest> = server
+            .calls_to("SQLExec")
+            .iter()
+            .map(|c| c.decode())
+            .collect();
+        assert!(!sent[0].no_wait);
+        assert!(sent[1].no_wait);
+        assert_eq!(sent[1].sql, "DELETE FROM t WHERE id = @id");
+        assert_eq!(sent[1].params[0].name, "id");
+    }
+
+    #[tokio::test]
+    async fn strict_params_match_referenced_placeholders() {
+        let server = exec_server().start().await;
+        let db = server.connect().await;
+        let mut sql = db.sql().strict_params(true);
+
+        // Case-insensitive; `@` in literals and quoted names isn't a param
+        sql.exec(
+            "UPDATE t SET note = 'mail@host', \"a@b\" = @Val WHERE id = @id",
+            Params::new().bind("val", 1).bind("ID", 2),
+        )
+        .await
+        .unwrap();
+        assert_eq!(server.calls_to("SQLExec").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn strict_params_reject_missing_and_extra_params() {
+        let server = exec_server().start().await;
+        let db = server.connect().await;
+        let mut sql = db.sql().strict_params(true);
+
+        let missing = sql
+            .exec("DELETE FROM t WHERE id = @id", Params::new())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&missing, Error::InvalidInput(m) if m.contains(r#"unbound ["id"]"#)),
+            "{missing:?}"
+        );
+        let extra = sql
+            .exec(
+                "DELETE FROM t WHERE id = @id",
+                Params::new().bind("id", 1).bind("idd", 1),
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&extra, Error::InvalidInput(m) if m.contains(r#"unused ["idd"]"#)),
+            "{extra:?}"
+        );
+        // Neither was sent
+        assert!(server.calls_to("SQLExec").is_empty());
+
+        // Off by default: left to the server
+        db.sql()
+            .exec("DELETE FROM t WHERE id = @id", Params::new())
+            .await
+            .unwrap();
+        assert_eq!(server.calls_to("SQLExec").len(), 1);
+    }
+
+    /// Transactions of `exec_batch`; statements containing `FAIL` fail
+    fn batch_server(pending: &Arc<Mutex<usize>>) -> Mock {
+        let (exec, commit, rollback) =
+            (pending.clone(), pending.clone(), pending.clone());
+        Mock::new()
+            .unary("NewTx", |_: NewTxRequest| {
+                Ok(NewTxResponse {
+                    transaction_id: "tx-7".into(),
+                })
+            })
+            .unary("TxSQLExec", move |req: SqlExecRequest| {
+                if req.sql.contains("FAIL") {
+                    return Err(tonic::Status::unknown("duplicate key"));
+                }
+                *exec.lock().unwrap() += 1;
+                Ok(())
+            })
+            .unary("Commit", move |_: ()| {
+                let rows = std::mem::take(&mut *commit.lock().unwrap());
+                Ok(CommittedSqlTx {
+                    header: Some(TxHeader {
+                        id: 42,
+                        ..Default::default()
+                    }),
+                    updated_rows: rows as u32,
+                    ..Default::default()
+                })
+            })
+            .unary("Rollback", move |_: ()| {
+                *rollback.lock().unwrap() = 0;
+                Ok(())
+            })
+    }
+
+    fn inserts(n: usize) -> Vec<(String, Params)> {
+        (0..n)
+            .map(|i| {
+                let sql = "INSERT INTO t (id) VALUES (@id)".to_string();
+                (sql, Params::new().bind("id", i as i64))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn exec_batch_runs_in_one_transaction() {
+        let pending = Arc::new(Mutex::new(0));
+        let server = batch_server(&pending).start().await;
+        let db = server.connect().await;
+
+        let outcomes = db.sql().exec_batch(inserts(100)).await.unwrap();
+
+        assert_eq!(server.calls_to("NewTx").len(), 1);
+        assert_eq!(server.calls_to("Commit").len(), 1);
+        assert!(server.calls_to("SQLExec").is_empty());
+        let execs = server.calls_to("TxSQLExec");
+        assert_eq!(execs.len(), 100);
+        assert!(execs.iter().all(|c| c.headers["transactionid"] == "tx-7"));
+        assert_eq!(outcomes.len(), 100);
+        assert!(outcomes.iter().all(|o| o.tx_id == Some(42)));
+        assert_eq!(outcomes[99].updated_rows, 100);
+    }
+
+    #[tokio::test]
+    async fn exec_batch_rolls_back_on_failure() {
+        let pending = Arc::new(Mutex::new(0));
+        let server = batch_server(&pending).start().await;
+        let db = server.connect().await;
+
+        let mut statements = inserts(10);
+        statements[5].0 = "INSERT INTO FAIL".into();
+        let err = db.sql().exec_batch(statements).await.unwrap_err();
+
+        assert!(matches!(err, Error::Protocol(_)), "{err:?}");
+        // Stopped at the failing statement
+        assert_eq!(server.calls_to("TxSQLExec").len(), 6);
+        assert_eq!(server.calls_to("Rollback").len(), 1);
+        assert!(server.calls_to("Commit").is_empty());
+        assert_eq!(*pending.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn query_from_a_spawned_task() {
+        let server = Mock::new()
+            .stream("SQLQuery", |_: SqlQueryRequest| {
+                Ok(vec![SqlQueryResult {
+                    columns: vec![ProtoColumn {
+                        name: "(n)".into(),
+                        r#type: "INTEGER".into(),
+                    }],
+                    rows: vec![ProtoRow {
+                        columns: vec!["(n)".into()],
+                        values: vec![int(7)],
+                    }],
+                }])
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        // Compiles only if the query future is Send
+        let task = tokio::spawn(async move {
+            let mut sql = db.sql();
+            sql.query_scalar::<i64>("SELECT 7 AS n", Params::new())
+                .await
+        });
+        assert_eq!(task.await.unwrap().unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn migrate_fresh_table_applies_in_version_order() {
+        let state = Arc::new(Mutex::new(Migrations::default()));
+        let server = migrations_server(&state).start().await;
+        let db = server.connect().await;
+
+        db.sql()
+            .migrate(&[
+                (2, "CREATE TABLE b (id INTEGER, PRIMARY KEY id)"),
+                (
+                    1,
+                    "CREATE TABLE a (id INTEGER, PRIMARY KEY id); \
+                     CREATE INDEX ON a(id)",
+                ),
+            ])
+            .await
+            .unwrap();
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.applied, [1, 2]);
+        assert_eq!(state.committed.len(), 5);
+        assert!(state.committed[0].starts_with("CREATE TABLE a"));
+        assert!(state.committed[1].starts_with("CREATE INDEX ON a"));
+        assert!(state.committed[3].starts_with("CREATE TABLE b"));
+        // Only the bookkeeping table is created outside a transaction
+        assert_eq!(state.direct.len(), 1);
+        assert!(state.direct[0].contains("IF NOT EXISTS _migrations"));
+    }
+
+    #[tokio::test]
+    async fn migrate_skips_applied_versions() {
+        let state = Arc::new(Mutex::new(Migrations {
+            applied: vec![1],
+            ..Default::default()
+        }));
+        let server = migrations_server(&state).start().await;
+        let db = server.connect().await;
+
+        db.sql()
+            .migrate(&[(1, "CREATE TABLE BROKEN"), (2, "CREATE TABLE b")])
+            .await
+            .unwrap();
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.applied, [1, 2]);
+        assert_eq!(state.committed[0], "CREATE TABLE b");
+        assert_eq!(server.calls_to("NewTx").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn migrate_retries_ddl_refused_in_a_transaction() {
+        let state = Arc::new(Mutex::new(Migrations::default()));
+        let server = migrations_server(&state).start().await;
+        let db = server.connect().await;
+
+        db.sql().migrate(&[(1, "CREATE DDL_ONLY")]).await.unwrap();
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.rollbacks, 1);
+        assert!(state.committed.is_empty());
+        assert_eq!(state.applied, [1]);
+        assert_eq!(state.direct[1], "CREATE DDL_ONLY");
+        assert!(state.direct[2].starts_with("INSERT INTO _migrations"));
+    }
+
+    #[tokio::test]
+    async fn migrate_returns_other_errors() {
+        let state = Arc::new(Mutex::new(Migrations::default()));
+        let server = migrations_server(&state).start().await;
+        let db = server.connect().await;
+
+        let err = db
+            .sql()
+            .migrate(&[(1, "CREATE TABLE BROKEN")])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::Protocol(s) if s.message() == "table does not exist"),
+            "{err:?}"
+        );
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.rollbacks, 1);
+        assert!(state.applied.is_empty());
+        // Not retried outside the transaction
+        assert_eq!(state.direct.len(), 1);
+    }
+
+    fn text(s: &str) -> SqlValue {
+        SqlValue {
+            value: Some(sql_value::Value::S(s.into())),
+        }
+    }
+
+    /// Result with `(name, type)` columns; rows carry the column names too
+    fn table(
+        cols: &[(&str, &str)],
+        rows: Vec<Vec<SqlValue>>,
+    ) -> SqlQueryResult {
+        SqlQueryResult {
+            columns: cols
+                .iter()
+                .map(|(name, ty)| ProtoColumn {
+                    name: (*name).into(),
+                    r#type: (*ty).into(),
+                })
+                .collect(),
+            rows: rows
+                .into_iter()
+                .map(|values| ProtoRow {
+                    columns: cols
+                        .iter()
+                        .map(|(name, _)| (*name).into())
+                        .collect(),
+                    values,
+                })
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn list_tables_returns_the_names() {
+        let server = Mock::new()
+            .unary("ListTables", |_: ()| {
+                Ok(table(
+                    &[("(tables.name)", "VARCHAR")],
+                    vec![vec![text("orders")], vec![text("users")]],
+                ))
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let tables = db.sql().list_tables().await.unwrap();
+        assert_eq!(tables, ["orders", "users"]);
+    }
+
+    #[tokio::test]
+    async fn describe_table_maps_the_catalog_columns() {
+        let server = Mock::new()
+            .unary("DescribeTable", |_: Table| {
+                let row = |name, ty, nullable| {
+                    vec![
+                        text(name),
+                        text(ty),
+                        SqlValue {
+                            value: Some(sql_value::Value::B(nullable)),
+                        },
+                        text("PRIMARY KEY"),
+                    ]
+                };
+                // Columns in a different order than COLUMN, TYPE
+                Ok(table(
+                    &[
+                        ("(users.TYPE)", "VARCHAR"),
+                        ("(users.COLUMN)", "VARCHAR"),
+                        ("(users.NULLABLE)", "BOOLEAN"),
+                        ("(users.INDEX)", "VARCHAR"),
+                    ],
+                    vec![
+                        row("INTEGER", "id", false),
+                        row("VARCHAR[256]", "email", true),
+                    ],
+                ))
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let columns = db.sql().describe_table("users").await.unwrap();
+        let described: Vec<_> = columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.r#type.as_str(), c.nullable))
+            .collect();
+        assert_eq!(
+            described,
+            [
+                ("id", "INTEGER", Some(false)),
+                ("email", "VARCHAR[256]", Some(true)),
+            ]
+        );
+        let req: Table = server.calls_to("DescribeTable")[0].decode();
+        assert_eq!(req.table_name, "users");
+    }
+
+    #[tokio::test]
+    async fn commit_tx_returns_the_committed_id() {
+        let pending = Arc::new(Mutex::new(0));
+        let server = batch_server(&pending).start().await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        let err = sql.commit_tx().await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{err:?}");
+
+        sql.begin(Transaction::ReadWrite).await.unwrap();
+        sql.exec("INSERT INTO t (id) VALUES (1)", Params::new())
+            .await
+            .unwrap();
+        assert_eq!(sql.commit_tx().await.unwrap(), 42);
+        let commit = &server.calls_to("Commit")[0];
+        assert_eq!(commit.headers["transactionid"], "tx-7");
+        // The transaction is over
+        assert!(matches!(sql.commit_tx().await, Err(Error::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn dyn_sql_client_drives_a_boxed_client() {
+        let pending = Arc::new(Mutex::new(0));
+        let server = batch_server(&pending)
+            .unary("SQLExec", |_: SqlExecRequest| Ok(SqlExecResult::default()))
+            .stream("SQLQuery", |_: SqlQueryRequest| {
+                Ok(vec![table(&[("(t.n)", "INTEGER")], vec![vec![int(7)]])])
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut sql: Box<dyn DynSqlClient> = Box::new(db.sql());
+
+        sql.exec("DELETE FROM t".into(), Params::new())
+            .await
+            .unwrap();
+        let qr = sql
+            .query("SELECT n FROM t".into(), Params::new())
+            .await
+            .unwrap();
+        assert_eq!(qr.scalar::<i64>().unwrap(), 7);
+
+        sql.begin(TxMode::ReadWrite).await.unwrap();
+        sql.exec("INSERT INTO t (n) VALUES (1)".into(), Params::new())
+            .await
+            .unwrap();
+        sql.commit().await.unwrap();
+        sql.begin(TxMode::ReadWrite).await.unwrap();
+        sql.rollback().await.unwrap();
+
+        assert_eq!(server.calls_to("SQLExec").len(), 1);
+        assert_eq!(server.calls_to("TxSQLExec").len(), 1);
+        assert_eq!(server.calls_to("Commit").len(), 1);
+        assert_eq!(server.calls_to("Rollback").len(), 1);
+    }
+
+    /// `users` rows with text that needs escaping in JSON, in two chunks
+    fn users_rows(
+        _: SqlQueryRequest,
+    ) -> std::result::Result<Vec<SqlQueryResult>, tonic::Status> {
+        let cols = [("(users.id)", "INTEGER"), ("(users.name)", "VARCHAR")];
+        Ok(vec![
+            table(&cols, vec![vec![int(1), text("say \"hi\"\n\tbye")]]),
+            table(&cols, vec![vec![int(2), text("back\\slash ünï")]]),
+        ])
+    }
+
+    fn users_server() -> Mock {
+        Mock::new().stream("SQLQuery", users_rows)
+    }
+
+    #[tokio::test]
+    async fn export_table_writes_escaped_json_lines() {
+        let server = users_server().start().await;
+        let db = server.connect().await;
+
+        let mut out = Vec::new();
+        let written = db.sql().export_table("users", &mut out).await.unwrap();
+        assert_eq!(written, 2);
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out,
+            "{\"id\":1,\"name\":\"say \\\"hi\\\"\\n\\tbye\"}\n\
+             {\"id\":2,\"name\":\"back\\\\slash ünï\"}\n"
+        );
+        let req: SqlQueryRequest = server.calls_to("SQLQuery")[0].decode();
+        assert_eq!(req.sql, "SELECT * FROM users");
+
+        let err = db
+            .sql()
+            .export_table("users; DROP TABLE users", &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn import_jsonl_round_trips_an_export() {
+        let pending = Arc::new(Mutex::new(0));
+        let server = batch_server(&pending)
+            .stream("SQLQuery", users_rows)
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        let mut exported = Vec::new();
+        sql.export_table("users", &mut exported).await.unwrap();
+        // Blank lines are skipped
+        exported.extend_from_slice(b"\n  \n");
+        let imported = sql
+            .import_jsonl("users", exported.as_slice())
+            .await
+            .unwrap();
+        assert_eq!(imported, 2);
+
+        let inserts: Vec<SqlExecRequest> = server
+            .calls_to("TxSQLExec")
+            .iter()
+            .map(|c| c.decode())
+            .collect();
+        assert_eq!(inserts.len(), 2);
+        assert_eq!(
+            inserts[0].sql,
+            "INSERT INTO users (id, name) VALUES (@id, @name)"
+        );
+        let values = |req: &SqlExecRequest| {
+            let mut params: Vec<_> = req
+                .params
+                .iter()
+                .map(|p| (p.name.clone(), p.value.clone().unwrap()))
+                .collect();
+            params.sort_by(|a, b| a.0.cmp(&b.0));
+            params
+        };
+        assert_eq!(
+            values(&inserts[0]),
+            [
+                ("id".to_string(), int(1)),
+                ("name".to_string(), text("say \"hi\"\n\tbye")),
+            ]
+        );
+        assert_eq!(
+            values(&inserts[1]),
+            [
+                ("id".to_string(), int(2)),
+                ("name".to_string(), text("back\\slash ünï")),
+            ]
+        );
+        assert_eq!(server.calls_to("Commit").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn import_jsonl_rejects_malformed_lines_before_writing() {
+        let pending = Arc::new(Mutex::new(0));
+        let server = batch_server(&pending).start().await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        let truncated = "{\"id\": 1}\n{\"id\": 2\n";
+        let err = sql.import_jsonl("users", truncated.as_bytes()).await;
+        assert!(matches!(err, Err(Error::JsonDecode(_))), "{err:?}");
+
+        let cases = [
+            ("{\"id\": 1}\n[1, 2]\n", "line 2: expected a JSON object"),
+            ("{\"id\": 1}\n{\"id\": 2, \"x\": 3}\n", "line 2: columns"),
+            ("{\"id; DROP\": 1}\n", "invalid column name"),
+        ];
+        for (lines, expected) in cases {
+            let err = sql.import_jsonl("users", lines.as_bytes()).await;
+            assert!(
+                matches!(&err, Err(Error::InvalidInput(m)) if m.contains(expected)),
+                "{lines:?}: {err:?}"
+            );
+        }
+        // Nothing of any input was sent
+        assert!(server.calls_to("NewTx").is_empty());
+        assert!(server.calls_to("TxSQLExec").is_empty());
+    }
+
+    #[tokio::test]
+    async fn upsert_returning_pk_returns_the_assigned_key() {
+        // A key is assigned only when the row doesn't give its own
+        let server = Mock::new()
+            .unary("SQLExec", |req: SqlExecRequest| {
+                let keyed = req.params.iter().any(|p| p.name == "id");
+                let pks = if keyed {
+                    HashMap::new()
+                } else {
+                    HashMap::from([("users".to_string(), int(5))])
+                };
+                Ok(SqlExecResult {
+                    txs: vec![CommittedSqlTx {
+                        last_inserted_p_ks: pks,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        let pk = sql
+            .upsert_returning_pk(
+                "users",
+                Params::new().bind("name", "ann").bind("age", 30),
+            )
+            .await
+            .unwrap();
+        assert_eq!(pk, int(5));
+        let req: SqlExecRequest = server.calls_to("SQLExec")[0].decode();
+        assert_eq!(
+            req.sql,
+            "UPSERT INTO users (name, age) VALUES (@name, @age)"
+        );
+
+        let err = sql
+            .upsert_returning_pk("users", Params::new().bind("id", 5))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Decode(_)), "{err:?}");
+
+        for (table, params) in [
+            ("users", Params::new()),
+            ("users;", Params::new().bind("name", "ann")),
+            ("users", Params::new().bind("na me", "ann")),
+        ] {
+            let err = sql.upsert_returning_pk(table, params).await;
+            assert!(matches!(err, Err(Error::InvalidInput(_))), "{err:?}");
+        }
+        assert_eq!(server.calls_to("SQLExec").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn add_and_rename_column_send_the_ddl() {
+        let server = Mock::new()
+            .unary("SQLExec", |_: SqlExecRequest| {
+                Ok(SqlExecResult {
+                    txs: vec![CommittedSqlTx {
+                        header: Some(TxHeader {
+                            id: 9,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        let added = sql
+            .add_column("users", "email", "VARCHAR[256]", false)
+            .await
+            .unwrap();
+        assert_eq!(added.tx_id, Some(9));
+        sql.add_column("users", "age", "INTEGER", true)
+            .await
+            .unwrap();
+        sql.rename_column("users", "email", "mail").await.unwrap();
+        let sent: Vec<String> = server
+            .calls_to("SQLExec")
+            .iter()
+            .map(|c| c.decode::<SqlExecRequest>().sql)
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                "ALTER TABLE users ADD COLUMN email VARCHAR[256] NOT NULL",
+                "ALTER TABLE users ADD COLUMN age INTEGER",
+                "ALTER TABLE users RENAME COLUMN email TO mail",
+            ]
+        );
+
+        // Nothing that isn't a plain identifier or type reaches the server
+        let rejected = [
+            sql.add_column("users", "a b", "INTEGER", true).await,
+            sql.add_column("users", "a", "INTEGER; DROP", true).await,
+            sql.add_column("users", "a", "VARCHAR[x]", true).await,
+            sql.rename_column("users", "email", "1mail").await,
+            sql.rename_column("users-x", "email", "mail").await,
+        ];
+        for res in rejected {
+            assert!(matches!(res, Err(Error::InvalidInput(_))), "{res:?}");
+        }
+        assert_eq!(server.calls_to("SQLExec").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn query_partial_keeps_the_rows_before_a_failure() {
+        let server = users_server()
+            .fail_stream("SQLQuery", tonic::Status::unavailable("node lost"))
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        let (qr, err) = sql
+            .query_partial("SELECT * FROM users", Params::new())
+            .await
+            .unwrap();
+        let ids: Vec<i64> = qr.first_col_as().unwrap();
+        assert_eq!(ids, [1, 2]);
+        assert_eq!(qr.column_names_normalized(), ["id", "name"]);
+        assert!(
+            matches!(&err, Some(Error::Protocol(s)) if s.message() == "node lost"),
+            "{err:?}"
+        );
+
+        // `query` gives up the rows
+        let err = sql
+            .query("SELECT * FROM users", Params::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Protocol(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn query_partial_without_a_failure_is_a_plain_query() {
+        let server = users_server().start().await;
+        let db = server.connect().await;
+
+        let (qr, err) = db
+            .sql()
+            .query_partial("SELECT * FROM users", Params::new())
+            .await
+            .unwrap();
+        assert_eq!(qr.len(), 2);
+        assert!(err.is_none(), "{err:?}");
+    }
+
+    /// `SELECT COUNT(*)`-like server: one value, or no rows for `EMPTY`
+    fn scalar_server(value: SqlValue) -> Mock {
+        Mock::new().stream("SQLQuery", move |req: SqlQueryRequest| {
+            let rows = if req.sql.contains("EMPTY") {
+                vec![]
+            } else {
+                vec![vec![value.clone()]]
+            };
+            Ok(vec![table(&[("(col0)", "INTEGER")], rows)])
+        })
+    }
+
+    #[tokio::test]
+    async fn exists_and_count_scalar() {
+        let server = scalar_server(int(3)).start().await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        assert!(sql.exists("SELECT 1 FROM t", Params::new()).await.unwrap());
+        assert!(
+            !sql.exists("SELECT 1 FROM EMPTY", Params::new())
+                .await
+                .unwrap()
+        );
+        let count = sql
+            .count_scalar(
+                "SELECT COUNT(*) FROM t WHERE n > @n",
+                Params::new().bind("n", 1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+        let req: SqlQueryRequest = server.calls_to("SQLQuery")[2].decode();
+        assert_eq!(req.sql, "SELECT COUNT(*) FROM t WHERE n > @n");
+        assert_eq!(req.params[0].name, "n");
+
+        let err = sql
+            .count_scalar("SELECT COUNT(*) FROM EMPTY", Params::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Decode(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn count_scalar_rejects_negative_values() {
+        let server = scalar_server(int(-1)).start().await;
+        let db = server.connect().await;
+
+        let err = db
+            .sql()
+            .count_scalar("SELECT -1", Params::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Decode(_)), "{err:?}");
+    }
+
+    fn header(id: u64) -> TxHeader {
+        TxHeader {
+            id,
+            ts: 1_700_000_000,
+            nentries: 1,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn last_tx_header_follows_execs_and_commits() {
+        let pending = Arc::new(Mutex::new(0));
+        let server = batch_server(&pending)
+            .unary("SQLExec", |_: SqlExecRequest| {
+                Ok(SqlExecResult {
+                    txs: vec![CommittedSqlTx {
+                        header: Some(header(9)),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+        assert!(sql.last_tx_header().is_none());
+
+        sql.exec("DELETE FROM t", Params::new()).await.unwrap();
+        assert_eq!(sql.last_tx_header(), Some(&header(9)));
+
+        // Not committed yet inside a transaction
+        sql.begin(Transaction::ReadWrite).await.unwrap();
+        sql.exec("DELETE FROM t", Params::new()).await.unwrap();
+        assert_eq!(sql.last_tx_header().map(|h| h.id), Some(9));
+        sql.commit().await.unwrap();
+        assert_eq!(sql.last_tx_header().map(|h| h.id), Some(42));
+    }
+
+    #[tokio::test]
+    async fn tx_info_asks_for_the_header_only() {
+        let server = Mock::new()
+            .unary("TxById", |req: TxRequest| {
+                Ok(crate::schema::Tx {
+                    header: (req.tx != 404).then(|| header(req.tx)),
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        assert_eq!(sql.tx_info(7).await.unwrap(), header(7));
+        let req: TxRequest = server.calls_to("TxById")[0].decode();
+        assert_eq!(req.tx, 7);
+        assert_eq!(req.entries_spec, Some(EntriesSpec::default()));
+
+        let err = sql.tx_info(404).await.unwrap_err();
+        assert!(matches!(err, Error::Decode(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn server_now_decodes_the_server_timestamp() {
+        let now = time::macros::datetime!(2024-05-01 12:30:15.250 UTC);
+        let micros = (now.unix_timestamp_nanos() / 1_000) as i64;
+        let server = scalar_server(SqlValue {
+            value: Some(sql_value::Value::Ts(micros)),
+        })
+        .start()
+        .await;
+        let db = server.connect().await;
+
+        assert_eq!(db.sql().server_now().await.unwrap(), now);
+        let req: SqlQueryRequest = server.calls_to("SQLQuery")[0].decode();
+        assert_eq!(req.sql, "SELECT NOW()");
+        assert!(req.params.is_empty());
+    }
+
+    #[tokio::test]
+    async fn exec_multi_runs_each_statement() {
+        // One updated row per statement; `BROKEN` fails
+        let server = Mock::new()
+            .unary("SQLExec", |req: SqlExecRequest| {
+                if req.sql.contains("BROKEN") {
+                    return Err(tonic::Status::unknown("table does not exist"));
+                }
+                Ok(SqlExecResult {
+                    txs: vec![CommittedSqlTx {
+                        updated_rows: 1,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+            })
+            .start()
+            .await;
+        let db = server.connect().await;
+
+        let script = "-- seed\nINSERT INTO t (id) VALUES (@id);\n\
+                      /* twice */ INSERT INTO t (id) VALUES (@id + 1);";
+        let outcomes = db
+            .sql()
+            .exec_multi(script, Params::new().bind("id", 1))
+            .await
+            .unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.iter().all(|o| o.updated_rows == 1));
+        let sent: Vec<SqlExecRequest> = server
+            .calls_to("SQLExec")
+            .iter()
+            .map(|c| c.decode())
+            .collect();
+        assert_eq!(sent[0].sql, "INSERT INTO t (id) VALUES (@id)");
+        assert_eq!(sent[1].sql, "INSERT INTO t (id) VALUES (@id + 1)");
+        assert!(sent.iter().all(|req| req.params[0].name == "id"));
+
+        // Strict: each statement gets only the params it references
+        db.sql()
+            .strict_params(true)
+            .exec_multi(
+                "DELETE FROM a WHERE id = @a; DELETE FROM b WHERE id = @b",
+                Params::new().bind("a", 1).bind("b", 2),
+            )
+            .await
+            .unwrap();
+        let sent = server.calls_to("SQLExec");
+        let names = |i: usize| -> Vec<String> {
+            let req: SqlExecRequest = sent[i].decode();
+            req.params.into_iter().map(|p| p.name).collect()
+        };
+        assert_eq!((names(2), names(3)), (vec!["a".into()], vec!["b".into()]));
+
+        let err = db
+            .sql()
+            .exec_multi("DELETE FROM BROKEN; DELETE FROM t", Params::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Protocol(_)), "{err:?}");
+        // Stopped at the failing statement
+        assert_eq!(server.calls_to("SQLExec").len(), 5);
+    }
+
+    #[tokio::test]
+    async fn query_map_maps_every_row() {
+        let server = users_server().start().await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        let users = sql
+            .query_map("SELECT * FROM users", Params::new(), |row, cols| {
+                let id: i64 = row.get(0)?;
+                let name: String = row.get_by_name("name", cols)?;
+                Ok((id, name.lines().count()))
+            })
+            .await
+            .unwrap();
+        assert_eq!(users, [(1, 2), (2, 1)]);
+
+        let err = sql
+            .query_map("SELECT * FROM users", Params::new(), |row, _| {
+                row.get::<bool>(0)
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Decode(_)), "{err:?}");
+    }
+
+    fn null() -> SqlValue {
+        SqlValue {
+            value: Some(sql_value::Value::Null(0)),
+        }
+    }
+
+    /// `people`: ann has an email, bob's is NULL
+    fn people() -> QueryResult {
+        QueryResult::from(table(
+            &[
+                ("(people.id)", "INTEGER"),
+                ("(people.name)", "VARCHAR"),
+                ("(people.email)", "VARCHAR"),
+            ],
+            vec![
+                vec![int(1), text("ann"), text("ann@example.com")],
+                vec![int(2), text("bob"), null()],
+            ],
+        ))
+    }
+
+    #[test]
+    fn deserialize_iter_converts_rows_lazily() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Person {
+            id: i64,
+            email: String,
+        }
+
+        let qr = people();
+        let mut people = qr.deserialize_iter::<Person>();
+        assert_eq!(
+            people.next().unwrap().unwrap(),
+            Person {
+                id: 1,
+                email: "ann@example.com".into()
+            }
+        );
+        // bob's NULL email only fails once reached
+        assert!(matches!(people.next(), Some(Err(Error::JsonDecode(_)))));
+        assert!(people.next().is_none());
+
+        let first: Vec<Person> = qr
+            .deserialize_iter()
+            .take(1)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert!(qr.rows_as::<Person>().is_err());
+    }
+
+    #[test]
+    fn into_rows_hands_over_the_rows() {
+        let rows = people().into_rows();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].values, [int(2), text("bob"), null()]);
+        assert_eq!(rows[1].columns[1], "(people.name)");
+
+        let ids: Vec<i64> = people()
+            .into_iter()
+            .map(|row| row.get(0).unwrap())
+            .collect();
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[test]
+    fn column_names_raw_and_normalized() {
+        let qr = QueryResult {
+            columns: vec![
+                Column::new("(people.id)", "INTEGER"),
+                Column::new("people.name", "VARCHAR"),
+                Column::new("(`email`)", "VARCHAR"),
+                Column::new("total", "INTEGER"),
+            ],
+            rows: vec![],
+        };
+        assert_eq!(
+            qr.column_names_raw(),
+            ["(people.id)", "people.name", "(`email`)", "total"]
+        );
+        assert_eq!(
+            qr.column_names_normalized(),
+            ["id", "name", "email", "total"]
+        );
+    }
+
+    #[test]
+    fn row_as_json_typed_carries_the_column_types() {
+        let qr = people();
+        assert_eq!(
+            qr.row_as_json_typed(1).unwrap(),
+            serde_json::json!({
+                "id": {"type": "INTEGER", "value": 2},
+                "name": {"type": "VARCHAR", "value": "bob"},
+                "email": {"type": "VARCHAR", "value": null},
+            })
+        );
+        assert!(matches!(qr.row_as_json_typed(2), Err(Error::Decode(_))));
+    }
+
+    #[test]
+    fn row_as_json_raw_keeps_the_server_names() {
+        // A join: both tables have an `id`
+        let qr = QueryResult::from(table(
+            &[("(a.id)", "INTEGER"), ("(b.id)", "INTEGER")],
+            vec![vec![int(1), int(2)]],
+        ));
+        assert_eq!(
+            qr.row_as_json_raw(0).unwrap(),
+            serde_json::json!({"(a.id)": 1, "(b.id)": 2})
+        );
+        // The normalized form keeps the second one qualified
+        assert_eq!(
+            qr.row_as_json(0).unwrap(),
+            serde_json::json!({"id": 1, "b.id": 2})
+        );
+    }
+
+    #[test]
+    fn row_get_by_index_and_name() {
+        let qr = people();
+        let (ann, bob) = (&qr.rows[0], &qr.rows[1]);
+        let cols = &qr.columns;
+
+        assert_eq!(ann.get::<i64>(0).unwrap(), 1);
+        assert!(matches!(ann.get::<i64>(3), Err(Error::Decode(_))));
+        assert!(matches!(ann.get::<i64>(1), Err(Error::Decode(_))));
+        for name in ["people.name", "(people.name)", "name", " name "] {
+            let got: String = ann.get_by_name(name, cols).unwrap();
+            assert_eq!(got, "ann", "{name}");
+        }
+        assert!(matches!(
+            ann.get_by_name::<String>("phone", cols),
+            Err(Error::Decode(m)) if m == "no column 'phone'"
+        ));
+        assert_eq!(bob.get_opt_by_name::<String>("email", cols).unwrap(), None);
+        assert_eq!(
+            ann.get_opt_by_name::<String>("email", cols)
+                .unwrap()
+                .as_deref(),
+            Some("ann@example.com")
+        );
+
+        // Short names must be unambiguous; qualified ones still work
+        let join = QueryResult::from(table(
+            &[("(a.id)", "INTEGER"), ("(b.id)", "INTEGER")],
+            vec![vec![int(1), int(2)]],
+        ));
+        let row = &join.rows[0];
+        assert_eq!(row.get_by_name::<i64>("b.id", &join.columns).unwrap(), 2);
+        assert!(matches!(
+            row.get_by_name::<i64>("id", &join.columns),
+            Err(Error::Decode(m)) if m.contains("ambiguous")
+        ));
+
+        // Without per-row labels the result's columns are used
+        let unlabeled = Row {
+            columns: vec![],
+            values: vec![int(5), text("eve")],
+        };
+        let name: String = unlabeled.get_by_name("name", cols).unwrap();
+        assert_eq!(name, "eve");
+    }
+
+    #[test]
+    fn nullable_is_set_where_a_null_was_seen() {
+        let nullable: Vec<_> =
+            people().columns.iter().map(|c| c.nullable).collect();
+        assert_eq!(nullable, [None, None, Some(true)]);
+
+        // No rows: nothing is known
+        let empty =
+            QueryResult::from(table(&[("(people.id)", "INTEGER")], vec![]));
+        assert_eq!(empty.columns[0].nullable, None);
+        assert_eq!(Column::new("id", "INTEGER").nullable, None);
+    }
+
+    /// Values of `params`, in binding order
+    fn bound(params: Params) -> Vec<sql_value::Value> {
+        params
+            .into_inner()
+            .into_iter()
+            .map(|p| p.value.and_then(|v| v.value).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn bind_json_maps_scalars_and_serializes_the_rest() {
+        use sql_value::Value::{B, F, N, Null, S};
+        let json = serde_json::json!([
+            null,
+            true,
+            7,
+            1.5,
+            "text",
+            [1, "a"],
+            {"k": {"n": null}},
+        ]);
+        let params = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .fold(Params::new(), |p, v| p.bind_json("v", v));
+        assert_eq!(
+            bound(params),
+            [
+                Null(0),
+                B(true),
+                N(7),
+                F(1.5),
+                S("text".into()),
+                S(r#"[1,"a"]"#.into()),
+                S(r#"{"k":{"n":null}}"#.into()),
+            ]
+        );
+        // Beyond i64: kept as a float
+        let big = serde_json::json!(u64::MAX);
+        assert_eq!(
+            bound(Params::new().bind_json("v", &big)),
+            [F(u64::MAX as f64)]
+        );
+    }
+
+    #[test]
+    fn datetimes_with_an_offset_round_trip_through_utc() {
+        use time::macros::{datetime, offset};
+
+        let local = datetime!(2024-05-01 14:30:00.000_250);
+        let params =
+            Params::new().bind_dt_with_offset("at", local, offset!(+2));
+        let [sql_value::Value::Ts(micros)] = bound(params)[..] else {
+            panic!("not a timestamp");
+        };
+        // Stored as UTC microseconds
+        let utc = datetime!(2024-05-01 12:30:00.000_250 UTC);
+        assert_eq!(micros as i128, utc.unix_timestamp_nanos() / 1_000);
+
+        let ts = SqlValue {
+            value: Some(sql_value::Value::Ts(micros)),
+        };
+        let back = decode_dt_in(ts.clone(), offset!(+2)).unwrap();
+        assert_eq!(back, local.assume_offset(offset!(+2)));
+        assert_eq!(back.offset(), offset!(+2));
+        assert_eq!(decode_dt_in(ts, offset!(-5)).unwrap().hour(), 7);
+
+        assert!(matches!(
+            decode_dt_in(int(1), offset!(+2)),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn epoch_binders_round_trip() {
+        use sql_value::Value::N;
+        let at = time::macros::datetime!(2024-05-01 12:00:00.789 +02:00);
+
+        let params = Params::new()
+            .bind_ts_secs("secs", at)
+            .bind_ts_millis("millis", at);
+        assert_eq!(bound(params), [N(1_714_557_600), N(1_714_557_600_789)]);
+
+        let secs = decode_ts_secs(int(1_714_557_600)).unwrap();
+        assert_eq!(secs, at.replace_millisecond(0).unwrap());
+        let millis = decode_ts_millis(int(1_714_557_600_789)).unwrap();
+        assert_eq!(millis, at);
+        assert!(matches!(decode_ts_secs(text("1")), Err(Error::Decode(_))));
+        assert!(matches!(
+            decode_ts_secs(int(i64::MAX)),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn text_bytes_bind_as_varchar() {
+        use sql_value::Value::S;
+
+        let params = Params::new().bind_utf8("s", "grüße".as_bytes()).unwrap();
+        assert_eq!(bound(params), [S("grüße".into())]);
+        let err = Params::new().bind_utf8("s", b"ok\xff").unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{err:?}");
+
+        let params = Params::new().bind_str_lossy("s", b"ok\xff");
+        assert_eq!(bound(params), [S("ok\u{fffd}".into())]);
+    }
+
+    #[test]
+    fn fixed_size_arrays_bind_as_bytes() {
+        use sql_value::Value::Bs;
+        let hash = [0xde, 0xad, 0xbe, 0xef];
+        let params = Params::new()
+            .bind("owned", hash)
+            .bind("borrowed", SqlArg::from(&hash));
+        assert_eq!(bound(params), [Bs(hash.to_vec()), Bs(hash.to_vec())]);
+    }
+
+    #[test]
+    fn shared_and_cow_strings_bind_as_varchar() {
+        use sql_value::Value::S;
+        let shared: Arc<str> = Arc::from("shared");
+        let params = Params::new()
+            .bind("arc_ref", &shared)
+            .bind("arc", shared.clone())
+            .bind("borrowed", Cow::Borrowed("cow"))
+            .bind("owned", Cow::<str>::Owned("owned".into()));
+        assert_eq!(
+            bound(params),
+            [
+                S("shared".into()),
+                S("shared".into()),
+                S("cow".into()),
+                S("owned".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn uuids_decode_from_bytes_and_text() {
+        use sql_value::Value::{Bs, S};
+        let id =
+            Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        let decode = |v| Uuid::try_from(SqlValue { value: Some(v) });
+
+        assert_eq!(decode(Bs(id.as_bytes().to_vec())).unwrap(), id);
+        assert_eq!(decode(Bs(id.to_string().into_bytes())).unwrap(), id);
+        assert_eq!(decode(S(id.to_string())).unwrap(), id);
+
+        let err = decode(Bs(vec![1, 2, 3])).unwrap_err();
+        assert!(matches!(&err, Error::Decode(m) if m.contains("3 bytes")));
+    }
+
+    #[tokio::test]
+    async fn params_are_reusable_by_reference() {
+        let server = users_server().start().await;
+        let db = server.connect().await;
+        let mut sql = db.sql();
+
+        let params = Params::new().bind("id", 1).bind("name", "ann");
+        sql.query("SELECT * FROM users WHERE id = @id", &params)
+            .await
+            .unwrap();
+        sql.query("SELECT * FROM users WHERE name = @name", &params)
+            .await
+            .unwrap();
+
+        let calls = server.calls_to("SQLQuery");
+        assert_eq!(calls.len(), 2);
+        for call in calls {
+            let req = call.decode::<SqlQueryRequest>();
+            assert_eq!(req.params, params.clone().into_inner());
+        }
+    }
+
+    /// One short and one long blob, a timestamp and a NULL
+    fn blobs() -> QueryResult {
+        use sql_value::Value::{Bs, Ts};
+        let ts = |us| SqlValue {
+            value: Some(Ts(us)),
+        };
+        let bs = |b: Vec<u8>| SqlValue { value: Some(Bs(b)) };
+        QueryResult::from(table(
+            &[
+                ("(blobs.id)", "INTEGER"),
+                ("(blobs.data)", "BLOB"),
+                ("(blobs.at)", "TIMESTAMP"),
+                ("(blobs.note)", "VARCHAR"),
+            ],
+            vec![
+                vec![int(1), bs(vec![0xab; 2]), ts(0), text("short")],
+                vec![
+                    int(20),
+                    bs(vec![0xcd; 20]),
+                    ts(1_700_000_000_000_000),
+                    null(),
+                ],
+            ],
+        ))
+    }
+
+    #[test]
+    fn display_renders_an_aligned_table() {
+        let hex = "cd".repeat(16);
+        assert_eq!(
+            blobs().to_string(),
+            format!(
+                "id | data{pad} | at                   | note\n\
+                 ---+-{dash}-+----------------------+------\n\
+                 1  | 0xabab{pad2} | 1970-01-01T00:00:00Z | short\n\
+                 20 | 0x{hex}… | 2023-11-14T22:13:20Z | NULL\n",
+                pad = " ".repeat(31),
+                dash = "-".repeat(35),
+                pad2 = " ".repeat(29),
+            )
+        );
+    }
+
+    #[test]
+    fn serialize_writes_an_array_of_row_objects() {
+        assert_eq!(
+            serde_json::to_value(people()).unwrap(),
+            serde_json::json!([
+                {"id": 1, "name": "ann", "email": "ann@example.com"},
+                {"id": 2, "name": "bob", "email": null},
+            ])
+        );
+    }
+
+    #[test]
+    fn scalar_accessors_and_their_errors() {
+        let people = people();
+        assert_eq!(people.scalar::<i64>().unwrap(), 1);
+        assert_eq!(people.first_col_as::<i64>().unwrap(), [1, 2]);
+        assert!(matches!(people.scalar::<bool>(), Err(Error::Decode(_))));
+
+        let empty = QueryResult::from(table(&[("(t.id)", "INTEGER")], vec![]));
+        assert!(matches!(empty.scalar::<i64>(), Err(Error::Decode(_))));
+        assert!(empty.first_col_as::<i64>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn one_as_requires_exactly_one_row() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Person {
+            id: i64,
+            name: String,
+            email: Option<String>,
+        }
+
+        let one = QueryResult::from(table(
+            &[("(people.id)", "INTEGER"), ("(people.name)", "VARCHAR")],
+            vec![vec![int(1), text("ann")]],
+        ));
+        assert_eq!(
+            one.one_as::<Person>().unwrap(),
+            Person {
+                id: 1,
+                name: "ann".into(),
+                email: None,
+            }
+        );
+
+        let err = people().one_as::<Person>().unwrap_err();
+        assert!(matches!(&err, Error::Decode(m) if m.contains("got 2")));
+    }
+
+    #[test]
+    fn uuid_columns_deserialize_into_uuid() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct User {
+            id: Uuid,
+            name: String,
+        }
+
+        let id =
+            Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        let qr = QueryResult::from(table(
+            &[("(users.id)", "UUID"), ("(users.name)", "VARCHAR")],
+            vec![vec![
+                SqlValue {
+                    value: Some(sql_value::Value::Bs(id.as_bytes().to_vec())),
+                },
+                text("ann"),
+            ]],
+        ));
+        assert_eq!(
+            qr.rows_as::<User>().unwrap(),
+            [User {
+                id,
+                name: "ann".into(),
+            }]
+        );
+    }
 }
@@ -0,0 +1,67 @@
+//! Throwaway immudb servers for integration tests, run with Docker through
+//! `testcontainers`. `tests/testing.rs` runs this example with
+//! `cargo test --features testing -- --ignored`.
+//!
+//! ```ignore
+//! use immudb_rs::document::builder::{CreateCollection, Field, SearchDocuments};
+//! use serde_json::json;
+//!
+//! #[tokio::test]
+//! async fn search_inserted_document() -> immudb_rs::Result<()> {
+//!     let (_container, db) = immudb_rs::testing::spawn_immudb().await?;
+//!     let mut doc = db.doc();
+//!     CreateCollection::name("users")
+//!         .field(Field::name("name").indexed(true).build())
+//!         .create(&mut doc)
+//!         .await?;
+//!     doc.insert_documents("users", vec![json!({"name": "alice"})])
+//!         .await?;
+//!     let query = json!({
+//!         "collection_name": "users",
+//!         "where": {"AND": [{"field": "name", "op": "EQ", "value": "alice"}]},
+//!     });
+//!     let found = SearchDocuments::query(query).execute(&doc).await?;
+//!     assert_eq!(found.len(), 1);
+//!     Ok(())
+//! }
+//! ```
+
+use std::time::Duration;
+
+use testcontainers::core::IntoContainerPort;
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage};
+
+use crate::error::Error;
+use crate::{ImmuDB, Result};
+
+pub const IMMUDB_IMAGE: &str = "codenotary/immudb";
+pub const IMMUDB_TAG: &str = "1.9.5";
+const GRPC_PORT: u16 = 3322;
+
+/// Start an immudb container and connect to it as `immudb`/`immudb` on
+/// `defaultdb`. The server is removed when the container is dropped, so
+/// keep it alive as long as the client.
+pub async fn spawn_immudb() -> Result<(ContainerAsync<GenericImage>, ImmuDB)> {
+    let container = GenericImage::new(IMMUDB_IMAGE, IMMUDB_TAG)
+        .with_exposed_port(GRPC_PORT.tcp())
+        .start()
+        .await
+        .map_err(|e| Error::Unexpected(format!("start immudb: {e}")))?;
+    let host = container
+        .get_host()
+        .await
+        .map_err(|e| Error::Unexpected(format!("container host: {e}")))?;
+    let port = container
+        .get_host_port_ipv4(GRPC_PORT)
+        .await
+        .map_err(|e| Error::Unexpected(format!("container port: {e}")))?;
+
+    // The port is mapped before immudb listens on it
+    let db = ImmuDB::builder()
+        .connect_retries(30)
+        .connect_retry_delay(Duration::from_millis(500))
+        .connect(&format!("http://{host}:{port}"))
+        .await?;
+    Ok((container, db))
+}
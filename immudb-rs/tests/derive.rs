@@ -0,0 +1,187 @@
+use immudb_rs::schema::SqlValue;
+use immudb_rs::schema::sql_value::Value;
+use immudb_rs::sql::{Column, FromRow as _, Params, Row, ToParams as _};
+use immudb_rs::{FromRow, ToParams};
+
+fn named(params: Params) -> Vec<(String, Option<Value>)> {
+    params
+        .into_inner()
+        .into_iter()
+        .map(|p| (p.name, p.value.and_then(|v| v.value)))
+        .collect()
+}
+
+fn param(name: &str, value: Value) -> (String, Option<Value>) {
+    (name.to_string(), Some(value))
+}
+
+fn s(v: &str) -> Value {
+    Value::S(v.into())
+}
+
+#[derive(ToParams)]
+enum OrderStatus {
+    Active,
+    #[sql(rename = "off")]
+    Disabled,
+}
+
+#[derive(ToParams)]
+#[sql(param = "kind")]
+enum Kind {
+    Person,
+}
+
+#[test]
+fn enum_binds_the_variant_name() {
+    assert_eq!(
+        named(OrderStatus::Active.to_params()),
+        [param("order_status", s("Active"))]
+    );
+    assert_eq!(
+        named(OrderStatus::Disabled.to_params()),
+        [param("order_status", s("off"))]
+    );
+    assert_eq!(
+        named(Kind::Person.to_params()),
+        [param("kind", s("Person"))]
+    );
+}
+
+#[derive(ToParams)]
+struct Filter<'a> {
+    #[sql(skip_if_empty)]
+    name: String,
+    #[sql(skip_if_empty)]
+    tags: Vec<u8>,
+    #[sql(skip_if_empty)]
+    city: &'a str,
+    /// `None` is NULL, `Some("")` is left out
+    #[sql(skip_if_empty)]
+    nick: Option<String>,
+    #[sql(skip_if_none, skip_if_empty)]
+    email: Option<String>,
+}
+
+#[test]
+fn skip_if_empty_leaves_out_empty_values() {
+    let empty = Filter {
+        name: String::new(),
+        tags: Vec::new(),
+        city: "",
+        nick: Some(String::new()),
+        email: Some(String::new()),
+    };
+    assert_eq!(named(empty.to_params()), []);
+
+    let none = Filter {
+        nick: None,
+        email: None,
+        ..empty
+    };
+    assert_eq!(named(none.to_params()), [param("nick", Value::Null(0))]);
+
+    let full = Filter {
+        name: "ann".into(),
+        tags: vec![1],
+        city: "Oslo",
+        nick: Some("A".into()),
+        email: Some("a@b".into()),
+    };
+    assert_eq!(
+        named(full.to_params()),
+        [
+            param("name", s("ann")),
+            param("tags", Value::Bs(vec![1])),
+            param("city", s("Oslo")),
+            param("nick", s("A")),
+            param("email", s("a@b")),
+        ]
+    );
+}
+
+/// Row the way a query returns it: qualified column names, values in
+/// column order
+fn row(values: Vec<(&str, Value)>) -> (Row, Vec<Column>) {
+    let cols: Vec<Column> = values
+        .iter()
+        .map(|(name, _)| Column::new(format!("(users.{name})"), ""))
+        .collect();
+    let row = Row {
+        columns: cols.iter().map(|c| c.name.clone()).collect(),
+        values: values
+            .into_iter()
+            .map(|(_, v)| SqlValue { value: Some(v) })
+            .collect(),
+    };
+    (row, cols)
+}
+
+#[derive(Debug, PartialEq, ToParams, FromRow)]
+struct User {
+    id: i64,
+    #[sql(rename = "user_name")]
+    name: String,
+}
+
+#[test]
+fn from_row_matches_renamed_columns() {
+    let (r, cols) = row(vec![("user_name", s("ann")), ("id", Value::N(7))]);
+    let user = User::from_row(&r, &cols).unwrap();
+    assert_eq!(
+        user,
+        User {
+            id: 7,
+            name: "ann".into()
+        }
+    );
+
+    // Same names both ways: parameters feed straight back into a row
+    let params = named(user.to_params());
+    let (r, cols) = row(params
+        .iter()
+        .map(|(name, v)| (name.as_str(), v.clone().unwrap()))
+        .collect());
+    assert_eq!(User::from_row(&r, &cols).unwrap(), user);
+
+    let (r, cols) = row(vec![("id", Value::N(7))]);
+    assert!(User::from_row(&r, &cols).is_err());
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Account {
+    id: uuid::Uuid,
+    email: Option<String>,
+    #[sql(skip)]
+    cached: Vec<String>,
+}
+
+#[test]
+fn from_row_skip_option_and_uuid_blob() {
+    let id = uuid::Uuid::from_u128(0x0123_4567_89ab_cdef);
+    let (r, cols) = row(vec![
+        ("id", Value::Bs(id.as_bytes().to_vec())),
+        ("email", Value::Null(0)),
+    ]);
+    assert_eq!(
+        Account::from_row(&r, &cols).unwrap(),
+        Account {
+            id,
+            email: None,
+            cached: Vec::new(),
+        }
+    );
+
+    // A skipped field isn't read even when a column has its name
+    let (r, cols) = row(vec![
+        ("id", Value::Bs(id.as_bytes().to_vec())),
+        ("email", s("a@b")),
+        ("cached", Value::N(1)),
+    ]);
+    let account = Account::from_row(&r, &cols).unwrap();
+    assert_eq!(account.email.as_deref(), Some("a@b"));
+    assert!(account.cached.is_empty());
+
+    let (r, cols) = row(vec![("id", Value::Bs(vec![1, 2])), ("email", s(""))]);
+    assert!(Account::from_row(&r, &cols).is_err());
+}
@@ -0,0 +1,106 @@
+// Against a real immudb in Docker: `cargo test --features testing -- --ignored`
+#![cfg(feature = "testing")]
+
+use immudb_rs::document::builder::{
+    Cmp, CreateCollection, DocQuery, Field, FieldType, InsertDocuments,
+    SearchDocuments,
+};
+use serde_json::json;
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn create_collection_and_search() -> immudb_rs::Result<()> {
+    let (_container, db) = immudb_rs::testing::spawn_immudb().await?;
+    let mut doc = db.doc();
+    CreateCollection::name("users")
+        .field(
+            Field::name("name")
+                .field_type(FieldType::String)
+                .indexed(true)
+                .build(),
+        )
+        .field(Field::name("age").field_type(FieldType::Integer).build())
+        .create(&mut doc)
+        .await?;
+    let inserted = InsertDocuments::collection("users")
+        .document(json!({"name": "alice", "age": 30}))
+        .document(json!({"name": "bob", "age": 25}))
+        .execute(&mut doc)
+        .await?;
+    assert_eq!(inserted.ids().len(), 2);
+
+    let query = DocQuery::collection("users")
+        .and(Cmp::eq("name", "alice"))
+        .build();
+    let found = SearchDocuments::query(query).execute(&doc).await?;
+    assert_eq!(found.len(), 1);
+    let alice = found[0].document.as_ref().unwrap();
+    assert_eq!(
+        alice.fields["age"].kind,
+        Some(prost_types::value::Kind::NumberValue(30.0))
+    );
+
+    db.close().await
+}
+
+/// `CreateCollection` refuses a declared id field because immudb does:
+/// sent as is, the same collection fails on the server
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn declared_id_fields_are_rejected() -> immudb_rs::Result<()> {
+    use immudb_rs::SessionInterceptor;
+    use immudb_rs::model::document_service_client::DocumentServiceClient;
+    use immudb_rs::model::{self, CreateCollectionRequest};
+    use immudb_rs::schema::Database;
+
+    let (container, db) = immudb_rs::testing::spawn_immudb().await?;
+    let mut doc = db.doc();
+    let err = CreateCollection::name("people")
+        .document_id_field_name("pid")
+        .field(Field::name("pid").field_type(FieldType::Integer).build())
+        .create(&mut doc)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, immudb_rs::Error::InvalidInput(_)), "{err:?}");
+
+    // Same request, bypassing the client-side check
+    let host = container.get_host().await.unwrap();
+    let port = container.get_host_port_ipv4(3322).await.unwrap();
+    let channel =
+        tonic::transport::Channel::from_shared(format!("http://{host}:{port}"))
+            .unwrap()
+            .connect()
+            .await?;
+    let token = db
+        .raw_service()
+        .use_database(Database {
+            database_name: "defaultdb".into(),
+        })
+        .await?
+        .into_inner()
+        .token;
+    let interceptor = SessionInterceptor::for_testing(
+        db.session_id(),
+        db.server_uuid(),
+        Some(&token),
+    )?;
+    let field = |name: &str, ty: model::FieldType| model::Field {
+        name: name.into(),
+        r#type: ty as i32,
+    };
+    let created = DocumentServiceClient::with_interceptor(channel, interceptor)
+        .create_collection(CreateCollectionRequest {
+            name: "people".into(),
+            document_id_field_name: "pid".into(),
+            fields: vec![
+                field("pid", model::FieldType::Integer),
+                field("name", model::FieldType::String),
+            ],
+            indexes: Vec::new(),
+        })
+        .await;
+    assert!(created.is_err(), "immudb accepted a declared id field");
+    assert!(doc.collections().await?.is_empty());
+
+    db.close().await
+}
@@ -0,0 +1,7 @@
+// Compile-time behavior of the ToParams/FromRow derives
+#[test]
+fn derives() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/pass/*.rs");
+    t.compile_fail("tests/ui/fail/*.rs");
+}
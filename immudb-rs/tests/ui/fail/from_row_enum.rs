@@ -0,0 +1,8 @@
+use immudb_rs::FromRow;
+
+#[derive(FromRow)]
+enum Status {
+    Active,
+}
+
+fn main() {}
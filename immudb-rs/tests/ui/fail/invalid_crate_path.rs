@@ -0,0 +1,9 @@
+use immudb_rs::ToParams;
+
+#[derive(ToParams)]
+#[sql(crate = "not a path")]
+struct User {
+    id: i64,
+}
+
+fn main() {}
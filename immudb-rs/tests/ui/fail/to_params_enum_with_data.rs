@@ -0,0 +1,9 @@
+use immudb_rs::ToParams;
+
+#[derive(ToParams)]
+enum Event {
+    Created,
+    Renamed(String),
+}
+
+fn main() {}
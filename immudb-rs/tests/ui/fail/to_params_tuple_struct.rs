@@ -0,0 +1,6 @@
+use immudb_rs::ToParams;
+
+#[derive(ToParams)]
+struct Pair(i64, String);
+
+fn main() {}
@@ -0,0 +1,33 @@
+use immudb_rs::{FromRow, ToParams};
+
+#[derive(ToParams, FromRow)]
+struct User {
+    id: i64,
+    #[sql(rename = "user_name")]
+    name: String,
+    #[sql(skip_if_none)]
+    email: Option<String>,
+    #[sql(skip_if_empty)]
+    bio: String,
+    #[sql(skip)]
+    cache: Vec<u8>,
+}
+
+#[derive(ToParams)]
+struct Borrowed<'a> {
+    #[sql(skip_if_empty)]
+    name: &'a str,
+}
+
+#[derive(ToParams)]
+#[sql(param = "status")]
+enum Status {
+    Active,
+    #[sql(rename = "off")]
+    Disabled,
+}
+
+fn main() {
+    let _ = immudb_rs::sql::Params::from(&Status::Active);
+    let _ = immudb_rs::sql::Params::from(&Borrowed { name: "" });
+}
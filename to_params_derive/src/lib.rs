@@ -22,6 +22,20 @@ use syn::{
 /// - `#[sql(rename = "...")]`
 /// - `#[sql(skip)]`
 /// - `#[sql(skip_if_none)]`
+/// - `#[sql(skip_if_empty)]` — for `String`/`Vec<_>`/`&str` (or
+///   `Option` of those), bind only non-empty values
+///
+/// Fieldless enums bind a single string parameter holding the variant
+/// name (or its `#[sql(rename = "...")]`). The parameter name defaults to
+/// the snake_cased type name and can be set with `#[sql(param = "...")]`:
+///
+/// ```ignore
+/// #[derive(ToParams)]
+/// #[sql(param = "status")]
+/// enum Status { Active, #[sql(rename = "off")] Disabled }
+///
+/// client.query("SELECT * FROM users WHERE status = @status", &Status::Active).await?;
+/// ```
 #[proc_macro_derive(ToParams, attributes(sql))]
 pub fn derive_to_params(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -29,6 +43,8 @@ pub fn derive_to_params(input: TokenStream) -> TokenStream {
     // ==== 1) Путь к крейту (по умолчанию ::immudb_rs), можно переопределить #[sql(crate="::mycrate")]
     let mut crate_path: Path =
         syn::parse_str("::immudb_rs").expect("crate path");
+    // Имя параметра для enum (по умолчанию — snake_case имени типа)
+    let mut enum_param: Option<String> = None;
 
     for attr in &input.attrs {
         if attr.path().is_ident("sql") {
@@ -41,6 +57,10 @@ pub fn derive_to_params(input: TokenStream) -> TokenStream {
                         })?;
                     crate_path = p;
                     Ok(())
+                } else if meta.path.is_ident("param") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    enum_param = Some(lit.value());
+                    Ok(())
                 } else {
                     // игнорируем незнакомые флаги на типе
                     Ok(())
@@ -65,10 +85,17 @@ pub fn derive_to_params(input: TokenStream) -> TokenStream {
                 .into();
             }
         },
+        Data::Enum(e) => {
+            let param = enum_param
+                .unwrap_or_else(|| to_snake_case(&input.ident.to_string()));
+            return derive_for_enum(&input, e, &crate_path, &param)
+                .unwrap_or_else(|e| e.to_compile_error())
+                .into();
+        }
         _ => {
             return syn::Error::new(
                 input.span(),
-                "ToParams can be derived only for structs",
+                "ToParams can be derived only for structs and fieldless enums",
             )
             .to_compile_error()
             .into();
@@ -91,6 +118,7 @@ pub fn derive_to_params(input: TokenStream) -> TokenStream {
         let mut skip = false;
         let mut rename: Option<String> = None;
         let mut skip_if_none = false;
+        let mut skip_if_empty = false;
 
         for attr in &f.attrs {
             if attr.path().is_ident("sql") {
@@ -101,6 +129,9 @@ pub fn derive_to_params(input: TokenStream) -> TokenStream {
                     } else if meta.path.is_ident("skip_if_none") {
                         skip_if_none = true;
                         Ok(())
+                    } else if meta.path.is_ident("skip_if_empty") {
+                        skip_if_empty = true;
+                        Ok(())
                     } else if meta.path.is_ident("rename") {
                         let lit: LitStr = meta.value()?.parse()?;
                         rename = Some(lit.value());
@@ -126,12 +157,33 @@ pub fn derive_to_params(input: TokenStream) -> TokenStream {
         // Если стоит #[sql(skip_if_none)] и тип поля Option<T> — генерим if let Some(...)
         let is_option = is_option_type(&f.ty);
 
-        if skip_if_none && is_option {
+        if skip_if_none && skip_if_empty && is_option {
+            bind_stmts.push(quote! {
+                if let Some(v) = &self.#field_ident {
+                    if !v.is_empty() {
+                        p = p.bind(#param_name, v.clone());
+                    }
+                }
+            });
+        } else if skip_if_none && is_option {
             bind_stmts.push(quote! {
                 if let Some(v) = &self.#field_ident {
                     p = p.bind(#param_name, v.clone());
                 }
             });
+        } else if skip_if_empty && is_option {
+            // None -> NULL, Some("") -> пропускаем
+            bind_stmts.push(quote! {
+                if !matches!(&self.#field_ident, Some(v) if v.is_empty()) {
+                    p = p.bind(#param_name, self.#field_ident.clone());
+                }
+            });
+        } else if skip_if_empty {
+            bind_stmts.push(quote! {
+                if !self.#field_ident.is_empty() {
+                    p = p.bind(#param_name, self.#field_ident.clone());
+                }
+            });
         } else {
             // обычный случай — просто clone() (Params::bind сейчас требует owned значения)
             bind_stmts.push(quote! {
@@ -141,7 +193,7 @@ pub fn derive_to_params(input: TokenStream) -> TokenStream {
     }
 
     let ty = &input.ident;
-    let (impl_generics, ty_generics, wc) = input.generics.split_for_impl();
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
     let wc = where_clause.as_ref().map(|w| w as &dyn ToTokens);
 
     let expanded = quote! {
@@ -157,6 +209,190 @@ pub fn derive_to_params(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Read query rows into structs, the counterpart of `ToParams`.
+///
+/// Columns are matched to fields by name (`users.id`, or just `id` when
+/// unambiguous) and converted with the `TryFrom<SqlValue>` impls;
+/// `Option<T>` fields are `None` for NULL. `#[sql(rename = "...")]` names
+/// the column, as it names the parameter for `ToParams`; `#[sql(skip)]`
+/// fields aren't read and take their `Default`:
+///
+/// ```ignore
+/// #[derive(ToParams, FromRow)]
+/// struct User { id: Uuid, #[sql(rename = "user_name")] name: String }
+///
+/// let users: Vec<User> = client.query("SELECT * FROM users", ()).await?.rows_into()?;
+/// ```
+#[proc_macro_derive(FromRow, attributes(sql))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_row_impl(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn from_row_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut crate_path: Path =
+        syn::parse_str("::immudb_rs").expect("crate path");
+    for attr in &input.attrs {
+        if attr.path().is_ident("sql") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("crate") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    crate_path = syn::parse_str(&lit.value()).map_err(|e| {
+                        meta.error(format!("invalid crate path: {e}"))
+                    })?;
+                } else if meta.input.peek(syn::Token![=]) {
+                    // `param = "..."` and friends belong to ToParams
+                    let _: LitStr = meta.value()?.parse()?;
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(n) => n,
+            _ => {
+                return Err(syn::Error::new(
+                    s.fields.span(),
+                    "FromRow supports only structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "FromRow can be derived only for structs",
+            ));
+        }
+    };
+
+    let mut inits = Vec::new();
+    for f in &fields.named {
+        let field_ident = f.ident.as_ref().ok_or_else(|| {
+            syn::Error::new(f.span(), "named fields expected")
+        })?;
+
+        let mut rename: Option<String> = None;
+        let mut skip = false;
+        for attr in &f.attrs {
+            if attr.path().is_ident("sql") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        rename = Some(lit.value());
+                    } else if meta.path.is_ident("skip") {
+                        skip = true;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+
+        if skip {
+            inits.push(quote! {
+                #field_ident: ::core::default::Default::default(),
+            });
+            continue;
+        }
+
+        let column = rename.unwrap_or_else(|| field_ident.to_string());
+        let getter = if is_option_type(&f.ty) {
+            quote! { get_opt_by_name }
+        } else {
+            quote! { get_by_name }
+        };
+        inits.push(quote! {
+            #field_ident: row.#getter(#column, cols)?,
+        });
+    }
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, wc) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #crate_path::sql::FromRow for #ty #ty_generics #wc {
+            fn from_row(
+                row: &#crate_path::sql::Row,
+                cols: &[#crate_path::sql::Column],
+            ) -> #crate_path::Result<Self> {
+                Ok(Self {
+                    #(#inits)*
+                })
+            }
+        }
+    })
+}
+
+// Enum без данных биндится одним строковым параметром:
+// значение — имя варианта или #[sql(rename = "...")]
+fn derive_for_enum(
+    input: &DeriveInput,
+    data: &syn::DataEnum,
+    crate_path: &Path,
+    param: &str,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut arms = Vec::new();
+
+    for v in &data.variants {
+        if !matches!(v.fields, Fields::Unit) {
+            return Err(syn::Error::new(
+                v.span(),
+                "ToParams on enums supports only fieldless variants",
+            ));
+        }
+
+        let mut rename: Option<String> = None;
+        for attr in &v.attrs {
+            if attr.path().is_ident("sql") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        rename = Some(lit.value());
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+
+        let ident = &v.ident;
+        let value = rename.unwrap_or_else(|| ident.to_string());
+        arms.push(quote! { Self::#ident => #value, });
+    }
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, wc) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #crate_path::sql::ToParams for #ty #ty_generics #wc {
+            fn to_params(&self) -> #crate_path::sql::Params {
+                let v: &'static str = match *self {
+                    #(#arms)*
+                };
+                #crate_path::sql::Params::new().bind(#param, v)
+            }
+        }
+    })
+}
+
+// "OrderStatus" -> "order_status"
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
 // Простая проверка: Option<T>?
 fn is_option_type(ty: &syn::Type) -> bool {
     if let syn::Type::Path(tp) = ty {